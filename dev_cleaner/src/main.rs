@@ -11,7 +11,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     
     if cli.gui {
-        DevCleanerGui::new(cli.path.clone()).run()?;
+        DevCleanerGui::new(config.roots.first().cloned())
+            .with_config(config)
+            .run()?;
         return Ok(());
     }
 
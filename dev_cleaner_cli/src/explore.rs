@@ -0,0 +1,230 @@
+//! Interactive disk usage explorer in the style of ncdu, see [`run`].
+
+use std::error::Error;
+
+use dev_cleaner_core::{
+    config::Config,
+    filters::{Filter, FilterMatcher, FilterRule},
+    sizetree::SizeTree,
+    usage::ByteSize,
+};
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Cell, Row, Table, TableState},
+};
+
+const HELP: &str = "↑↓ move  enter/→ open  ←/backspace up  f add name as filter  q quit";
+const BAR_WIDTH: usize = 10;
+
+struct Explorer<'a, M> {
+    tree: SizeTree,
+    /// Child indices from the root to the directory being shown.
+    current: Vec<usize>,
+    /// Cursor positions of the parent directories, restored when going up.
+    cursors: Vec<usize>,
+    state: TableState,
+    status: Option<String>,
+    config: &'a mut Config,
+    matcher: M,
+}
+
+/// Lets the user navigate `tree`. Folder names can be added as filters of
+/// `config`, after which the tree is matched again with `matcher`.
+pub fn run<M>(tree: SizeTree, config: &mut Config, matcher: M) -> Result<(), Box<dyn Error>>
+where
+    M: Fn(&Config) -> Result<FilterMatcher, Box<dyn Error>>,
+{
+    let mut explorer = Explorer {
+        tree,
+        current: Vec::new(),
+        cursors: Vec::new(),
+        state: TableState::default().with_selected(0),
+        status: None,
+        config,
+        matcher,
+    };
+
+    let mut terminal = ratatui::init();
+    let result = explorer.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl<M> Explorer<'_, M>
+where
+    M: Fn(&Config) -> Result<FilterMatcher, Box<dyn Error>>,
+{
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Box<dyn Error>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                return Ok(());
+            }
+
+            self.status = None;
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Up | KeyCode::Char('k') => self.state.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => self.state.select_next(),
+                KeyCode::PageUp => self.state.scroll_up_by(20),
+                KeyCode::PageDown => self.state.scroll_down_by(20),
+                KeyCode::Home => self.state.select_first(),
+                KeyCode::End => self.state.select_last(),
+                KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => self.open(),
+                KeyCode::Left | KeyCode::Backspace | KeyCode::Char('h') => self.up(),
+                KeyCode::Char('f') => self.add_filter(),
+                _ => {}
+            }
+        }
+    }
+
+    fn directory(&self) -> &SizeTree {
+        self.tree.get(&self.current).unwrap_or(&self.tree)
+    }
+
+    /// The subdirectory under the cursor, `None` on the line for loose files.
+    fn selected(&self) -> Option<&SizeTree> {
+        self.directory().children.get(self.state.selected()?)
+    }
+
+    fn open(&mut self) {
+        let Some(i) = self.state.selected() else {
+            return;
+        };
+        if self
+            .selected()
+            .is_some_and(|child| !child.children.is_empty())
+        {
+            self.current.push(i);
+            self.cursors.push(i);
+            self.state.select(Some(0));
+        }
+    }
+
+    fn up(&mut self) {
+        if self.current.pop().is_some() {
+            self.state.select(self.cursors.pop());
+        }
+    }
+
+    /// Adds the name of the selected folder as a filter and matches the tree again.
+    fn add_filter(&mut self) {
+        let Some(name) = self.selected().map(|child| child.name().to_string()) else {
+            return;
+        };
+        let rule = FilterRule::Name(name.clone());
+        if self.config.filters.iter().any(|filter| filter.rule == rule) {
+            self.status = Some(format!("`{name}` already is a filter"));
+            return;
+        }
+
+        self.config.filters.push(Filter::new(rule));
+        let result = self
+            .config
+            .store()
+            .map_err(Box::from)
+            .and_then(|_| (self.matcher)(self.config));
+        self.status = Some(match result {
+            Ok(filters) => {
+                self.tree.rematch(&filters);
+                format!(
+                    "Added filter `{name}`, filters now match {}",
+                    ByteSize(self.tree.matched_usage().allocated_bytes)
+                )
+            }
+            Err(e) => format!("Could not add filter `{name}`: {e}"),
+        });
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let directory = self.directory();
+        let total = directory.usage.allocated_bytes.max(1);
+        frame.render_widget(
+            Line::from(format!(
+                " {} · {} · {} total, {} matched by filters",
+                dev_cleaner_core::APP_NAME,
+                directory.path.display(),
+                ByteSize(directory.usage.allocated_bytes),
+                ByteSize(directory.matched_usage().allocated_bytes)
+            ))
+            .bold(),
+            header,
+        );
+
+        let row = |size: u64, name: String, rule: String| {
+            let filled = (size as u128 * BAR_WIDTH as u128 / total as u128) as usize;
+            Row::new([
+                Cell::from(ByteSize(size).to_string()),
+                Cell::from(format!(
+                    "{:>5.1}% [{}{}]",
+                    size as f64 * 100.0 / total as f64,
+                    "#".repeat(filled),
+                    " ".repeat(BAR_WIDTH - filled)
+                )),
+                Cell::from(name),
+                Cell::from(rule),
+            ])
+        };
+        let mut rows = directory
+            .children
+            .iter()
+            .map(|child| {
+                let rule = child.reason.as_ref().map(ToString::to_string);
+                let row = row(
+                    child.usage.allocated_bytes,
+                    format!("{}/", child.name()),
+                    rule.clone().unwrap_or_default(),
+                );
+                match rule {
+                    Some(_) => row.fg(Color::Yellow),
+                    None => row,
+                }
+            })
+            .collect::<Vec<_>>();
+        let children: u64 = directory
+            .children
+            .iter()
+            .map(|child| child.usage.allocated_bytes)
+            .sum();
+        let files = directory.usage.allocated_bytes.saturating_sub(children);
+        if files > 0 {
+            rows.push(row(files, "(files)".to_string(), String::new()).fg(Color::DarkGray));
+        }
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(11),
+                Constraint::Length(BAR_WIDTH as u16 + 9),
+                Constraint::Fill(1),
+                Constraint::Length(24),
+            ],
+        )
+        .block(Block::bordered())
+        .row_highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(table, body, &mut self.state);
+
+        let footer_line = match &self.status {
+            Some(status) => Line::from(status.as_str()).fg(Color::Cyan),
+            None => Line::from(HELP).fg(Color::DarkGray),
+        };
+        frame.render_widget(footer_line, footer);
+    }
+}
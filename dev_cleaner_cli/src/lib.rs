@@ -1,12 +1,33 @@
-use clap::{Parser, Subcommand};
+mod explore;
+mod listing;
+mod tui;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use log::{LevelFilter, debug};
-use owo_colors::OwoColorize;
-use std::path::PathBuf;
+use owo_colors::{AnsiColors, OwoColorize, Style};
+use std::{
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+};
 
 use dev_cleaner_core::{
     config::{self, Config},
-    folderscan::{remove_folders, scan_folders},
+    export::ScanReport,
+    filters::{Filter, FilterMatcher, FilterRule, Marker},
+    folderscan::{
+        CancellationToken, FoundFolder, ScanEvent, dedupe_roots, remove_folders, root_of,
+        scan_roots_streaming,
+    },
+    mounts::MountPolicy,
+    presets::Preset,
+    remover::{DryRun, Removal, RemovalMethod, RemovalReport},
+    safety::{SafetyGuard, Violation},
+    select::Selection,
+    sizetree::SizeTree,
+    stats::{Bucket, ScanStats},
+    usage::{Age, ByteSize, DiskUsage},
 };
+use listing::{ListArgs, Listing, display_path, paint};
 
 #[derive(Debug, Parser)]
 #[clap(author, version, about, long_about = None,
@@ -14,10 +35,11 @@ use dev_cleaner_core::{
     subcommand_precedence_over_arg = true
 )]
 pub struct DevCleanerCli {
-    /// Optional path argument
-    #[arg(value_hint = clap::ValueHint::DirPath)]
-    #[arg(index = 1)]
-    pub path: Option<PathBuf>,
+    #[command(flatten)]
+    pub scan: ScanArgs,
+
+    #[command(flatten)]
+    pub clean: CleanArgs,
 
     /// Set log level (e.g., DEBUG, INFO, WARN, ERROR)
     #[arg(short, long, value_enum, default_value = "info")]
@@ -25,13 +47,92 @@ pub struct DevCleanerCli {
 
     /// Run in GUI mode
     #[arg(long, default_value = "false")]
-    #[arg(conflicts_with = "path")]
+    #[arg(conflicts_with = "paths")]
     pub gui: bool,
 
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
+/// How found folders are chosen and removed.
+#[derive(Debug, Args)]
+pub struct CleanArgs {
+    /// Move folders to the trash instead of deleting them
+    #[arg(long, conflicts_with = "delete")]
+    pub trash: bool,
+
+    /// Delete folders permanently, even if the trash is configured
+    #[arg(long)]
+    pub delete: bool,
+
+    /// Clean the selected folders without asking for confirmation
+    #[arg(short = 'y', long, requires = "selection")]
+    pub yes: bool,
+
+    /// Select every found folder instead of prompting
+    #[arg(long, group = "selection")]
+    pub all: bool,
+
+    /// Select the found folders matching an expression instead of prompting
+    /// (e.g. 'size>1G && age>14d', fields: size, age, files, rule, name, path)
+    #[arg(long, value_name = "EXPR", group = "selection")]
+    pub select: Option<Selection>,
+
+    /// Show what would be removed without touching anything
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+
+    #[command(flatten)]
+    pub list: ListArgs,
+}
+
+/// What to scan and which folders count as matches.
+#[derive(Debug, Args)]
+pub struct ScanArgs {
+    /// Folders to scan, the configured roots if omitted
+    #[arg(value_hint = clap::ValueHint::DirPath)]
+    #[arg(index = 1)]
+    pub paths: Vec<PathBuf>,
+
+    /// Number of scanner threads (0 = one per core, 1 = single-threaded)
+    #[arg(short = 'j', long)]
+    pub threads: Option<usize>,
+
+    /// Only clean folders whose project was not modified for this long (e.g. 30d, 2w)
+    #[arg(long, value_name = "AGE")]
+    pub older_than: Option<Age>,
+
+    /// Hide folders smaller than this (e.g. 10M, 1.5G)
+    #[arg(long, value_name = "SIZE")]
+    pub min_size: Option<ByteSize>,
+
+    /// Also clean any folder containing a valid CACHEDIR.TAG
+    #[arg(long)]
+    pub cachedir_tags: bool,
+
+    /// Stay on the filesystem of the scanned folder
+    #[arg(short = 'x', long)]
+    pub one_file_system: bool,
+
+    /// Also skip mounts of this filesystem type (e.g. `ext4`, `fuse.*`)
+    #[arg(long = "skip-fs", value_name = "TYPE")]
+    pub skip_fs: Vec<String>,
+}
+
+/// Output formats of the `scan` command.
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// A single JSON document with all results
+    Json,
+    /// One JSON object per found folder and line
+    Ndjson,
+    /// One CSV row per found folder, with a header
+    Csv,
+    /// An aligned table for reading
+    #[default]
+    Table,
+}
+
 #[derive(Debug, Subcommand)]
 pub enum Commands {
     /// Manage configuration
@@ -40,6 +141,54 @@ pub enum Commands {
         #[command(subcommand)]
         command: Option<ConfigCommands>,
     },
+    /// List matching folders without removing anything
+    #[command(visible_alias = "s")]
+    Scan {
+        #[command(flatten)]
+        scan: ScanArgs,
+        /// How results are printed
+        #[arg(short, long, value_enum, default_value_t)]
+        format: OutputFormat,
+        /// Also write the results as a plan for `clean --plan`
+        #[arg(short, long, value_name = "FILE")]
+        out: Option<PathBuf>,
+        #[command(flatten)]
+        list: ListArgs,
+    },
+    /// Sum up reclaimable space per ecosystem, rule, root and project
+    #[command(visible_alias = "st")]
+    Stats {
+        #[command(flatten)]
+        scan: ScanArgs,
+        /// Number of largest entries shown per breakdown
+        #[arg(short, long, value_name = "N", default_value_t = 10)]
+        top: usize,
+    },
+    /// Browse matching folders in a full-screen terminal UI and remove them
+    #[command(visible_alias = "ui")]
+    Tui {
+        #[command(flatten)]
+        scan: ScanArgs,
+        #[command(flatten)]
+        clean: CleanArgs,
+    },
+    /// Explore what takes up space below a folder, ncdu style
+    #[command(visible_alias = "x")]
+    Explore {
+        #[arg(value_hint = clap::ValueHint::DirPath)]
+        path: PathBuf,
+    },
+    /// Choose among matching folders and remove them
+    Clean {
+        #[command(flatten)]
+        scan: ScanArgs,
+        #[command(flatten)]
+        clean: CleanArgs,
+        /// Remove the folders of a plan written by `scan --out` instead of scanning,
+        /// refusing those that changed since or lie outside the given folders
+        #[arg(long, value_name = "FILE", group = "selection")]
+        plan: Option<PathBuf>,
+    },
 }
 #[derive(Debug, Subcommand)]
 pub enum ConfigCommands {
@@ -49,6 +198,48 @@ pub enum ConfigCommands {
         #[command(subcommand)]
         command: Option<FilterCommands>,
     },
+    /// Built-in filter bundles per ecosystem
+    #[command(visible_alias = "pr")]
+    Presets {
+        #[command(subcommand)]
+        command: Option<PresetCommands>,
+    },
+    /// Set whether folders containing a valid CACHEDIR.TAG are matched
+    #[command(name = "cachedir-tags")]
+    CachedirTags {
+        #[arg(action = clap::ArgAction::Set)]
+        enabled: bool,
+    },
+    /// Set whether scans stay on the filesystem of the scanned folder
+    #[command(name = "same-filesystem")]
+    SameFilesystem {
+        #[arg(action = clap::ArgAction::Set)]
+        enabled: bool,
+    },
+    /// Filesystem types that are never scanned into
+    #[command(name = "skip-fs")]
+    SkipFs {
+        #[command(subcommand)]
+        command: Option<SkipFsCommands>,
+    },
+    /// Set how folders are removed (`delete` or `trash`)
+    Removal { method: RemovalMethod },
+    /// Set the minimum age of folders to clean (e.g. 30d), omit to clear it
+    MinAge { age: Option<Age> },
+    /// Set the minimum size of folders to clean (e.g. 10M), omit to clear it
+    MinSize { size: Option<ByteSize> },
+    /// Folders scanned when none are given
+    #[command(visible_alias = "r")]
+    Roots {
+        #[command(subcommand)]
+        command: Option<RootCommands>,
+    },
+    /// Paths that are never removed
+    #[command(visible_alias = "p")]
+    Protected {
+        #[command(subcommand)]
+        command: Option<ProtectedCommands>,
+    },
     /// List all folder patterns
     #[command(visible_alias = "ls")]
     List,
@@ -58,12 +249,23 @@ pub enum ConfigCommands {
 
 #[derive(Debug, Subcommand)]
 pub enum FilterCommands {
-    /// Add a folder pattern
+    /// Add a folder pattern (`name:`, `glob:`, `regex:` or `path:` prefixed)
     #[command(visible_alias = "+")]
-    Add { pattern: String },
+    Add {
+        pattern: FilterRule,
+        /// Only match next to this file (`sibling:` default) or containing it (`contains:`)
+        #[arg(short, long = "marker")]
+        markers: Vec<Marker>,
+        /// Only match folders whose project was not modified for this long
+        #[arg(long, value_name = "AGE")]
+        older_than: Option<Age>,
+        /// Only match folders of at least this size
+        #[arg(long, value_name = "SIZE")]
+        min_size: Option<ByteSize>,
+    },
     /// Remove a folder pattern
     #[command(visible_aliases = ["rm", "-"])]
-    Remove { pattern: String },
+    Remove { pattern: FilterRule },
     /// List all folder patterns
     #[command(visible_alias = "ls")]
     List,
@@ -71,6 +273,72 @@ pub enum FilterCommands {
     Reset,
 }
 
+#[derive(Debug, Subcommand)]
+pub enum PresetCommands {
+    /// Use the filters of a preset (e.g. `rust`, `node`, `jvm`)
+    #[command(visible_alias = "+")]
+    Enable { preset: Preset },
+    /// Stop using the filters of a preset
+    #[command(visible_aliases = ["rm", "-"])]
+    Disable { preset: Preset },
+    /// List all presets and their filters
+    #[command(visible_alias = "ls")]
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SkipFsCommands {
+    /// Skip mounts of a filesystem type, globs allowed (e.g. `fuse.*`)
+    #[command(visible_alias = "+")]
+    Add { fs_type: String },
+    /// Scan into mounts of a filesystem type again
+    #[command(visible_aliases = ["rm", "-"])]
+    Remove { fs_type: String },
+    /// List all skipped filesystem types
+    #[command(visible_alias = "ls")]
+    List,
+    /// Restore the default skipped filesystem types
+    Reset,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RootCommands {
+    /// Scan a folder when none are given
+    #[command(visible_alias = "+")]
+    Add {
+        #[arg(value_hint = clap::ValueHint::DirPath)]
+        path: PathBuf,
+    },
+    /// Stop scanning a folder by default
+    #[command(visible_aliases = ["rm", "-"])]
+    Remove {
+        #[arg(value_hint = clap::ValueHint::DirPath)]
+        path: PathBuf,
+    },
+    /// List all default folders
+    #[command(visible_alias = "ls")]
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ProtectedCommands {
+    /// Protect a path and everything inside it
+    #[command(visible_alias = "+")]
+    Add {
+        #[arg(value_hint = clap::ValueHint::DirPath)]
+        path: PathBuf,
+    },
+    /// Stop protecting a path
+    #[command(visible_aliases = ["rm", "-"])]
+    Remove {
+        #[arg(value_hint = clap::ValueHint::DirPath)]
+        path: PathBuf,
+    },
+    /// List all protected paths
+    #[command(visible_alias = "ls")]
+    List,
+}
+
 impl DevCleanerCli {
     /// Parse command line arguments
     pub fn parse_args() -> DevCleanerCli {
@@ -78,58 +346,19 @@ impl DevCleanerCli {
     }
     /// Process the command
     pub fn process(&self, config: &mut config::Config) -> Result<(), Box<dyn std::error::Error>> {
-        if self.path.is_none() {
-            Self::show_about()?;
-            return Ok(());
+        if let Some(cmd) = self.command.as_ref() {
+            return cmd.process(config);
         }
 
-        if let Some(path_buf) = &self.path {
-            // FIXME: target dir passing bodge
-            let found_folders = scan_folders(
-                path_buf,
-                config
-                    .filters
-                    .iter()
-                    .map(|f| f.as_str())
-                    .collect::<Vec<_>>()
-                    .as_slice(),
-            );
-
-            let items = found_folders
-                .iter()
-                .map(|f| format!("{}", f.display()))
-                .collect::<Vec<_>>();
-
-            let selected =
-                dialoguer::MultiSelect::with_theme(&dialoguer::theme::ColorfulTheme::default())
-                    .with_prompt(format!("Found {} folders to clean", found_folders.len()))
-                    .items(items.as_slice())
-                    .report(true)
-                    .interact()?;
-
-            let selected_folders = selected
-                .iter()
-                .map(|i| found_folders[*i].clone())
-                .collect::<Vec<_>>();
-
-            let (removed, errored) = remove_folders(selected_folders);
-
-            println!("Removed {} folders", removed.len());
-            for f in removed {
-                println!("\t- {}", f.display().green());
-            }
-            println!("Errored {} folders", errored.len());
-            for f in errored {
-                println!("\t- {}", f.display().red());
-            }
+        if self.scan.paths.is_empty() && config.roots.is_empty() {
+            Self::show_about()?;
+            return Ok(());
         }
+        let roots = self.scan.roots(config)?;
 
-        let _ = match self.command.as_ref() {
-            Some(cmd) => cmd.process(config),
-            None => Ok(()),
-        };
-        Ok(())
+        self.clean.clean(&self.scan, roots, config)
     }
+
     fn show_about() -> Result<(), Box<dyn std::error::Error>> {
         let font =
             figlet_rs::FIGfont::from_file("dev_cleaner_cli/resources/fonts/Tubes-Smushed.flf")?;
@@ -171,13 +400,405 @@ impl DevCleanerCli {
         println!("Please file any issues here: ");
         println!("{}", issues.green().underline());
         println!("\n");
-        println!("Press [Enter] to continue...");
+        if std::io::stdin().is_terminal() {
+            println!("Press [Enter] to continue...");
+            let mut buf = String::new();
+            std::io::stdin().read_line(&mut buf)?;
+        }
+
+        Ok(())
+    }
+}
 
-        let mut buf = String::new();
-        std::io::stdin().read_line(&mut buf)?;
+impl CleanArgs {
+    /// Scans `roots`, lets the user choose among the found folders and removes them.
+    pub fn clean(
+        &self,
+        scan: &ScanArgs,
+        roots: Vec<PathBuf>,
+        config: &Config,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(found_folders) = scan.scan(&roots, config, self.uses_age())? else {
+            println!("Scan cancelled");
+            return Ok(());
+        };
 
+        if roots.len() > 1 {
+            Self::print_roots(&roots, &found_folders);
+        }
+        let Some(selected_folders) = self.choose(&found_folders, &roots, self.all)? else {
+            return Ok(());
+        };
+        self.remove(selected_folders, roots, scan.matcher(config)?, config);
         Ok(())
     }
+
+    /// Removes the folders of a plan written by `scan --out`. Entries whose
+    /// folder was replaced or no longer matches its rule are refused.
+    ///
+    /// The roots recorded in the plan are not trusted, entries have to lie
+    /// below `roots`, given on the command line or configured.
+    pub fn clean_plan(
+        &self,
+        plan: &Path,
+        scan: &ScanArgs,
+        roots: Vec<PathBuf>,
+        config: &Config,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let plan = ScanReport::load(plan)?;
+        let filters = scan.matcher(config)?.with_project_age(self.uses_age());
+
+        let mut folders = Vec::new();
+        let mut stale = Vec::new();
+        for record in &plan.folders {
+            if root_of(&record.path, &roots).is_none() {
+                stale.push((record, Violation::OutsideRoots));
+                continue;
+            }
+            match record.verify(&filters) {
+                Ok(folder) => folders.push(folder),
+                Err(violation) => stale.push((record, violation)),
+            }
+        }
+        if !stale.is_empty() {
+            println!("Refused {} stale plan entries", stale.len());
+            for (record, violation) in &stale {
+                println!("\t- {} ({violation})", display_path(&record.path).yellow());
+            }
+        }
+
+        let Some(selected_folders) = self.choose(&folders, &roots, true)? else {
+            return Ok(());
+        };
+        self.remove(selected_folders, roots, filters, config);
+        Ok(())
+    }
+
+    /// Returns `true` if listing or selecting folders needs the age of their project.
+    fn uses_age(&self) -> bool {
+        self.list.uses_age() || self.select.as_ref().is_some_and(Selection::uses_age)
+    }
+
+    /// Removes `folders` below `roots` with this run's removal method.
+    fn remove(
+        &self,
+        folders: Vec<FoundFolder>,
+        roots: Vec<PathBuf>,
+        filters: FilterMatcher,
+        config: &Config,
+    ) {
+        let method = self.removal_method(config);
+        let remover = match self.dry_run {
+            true => Box::new(DryRun),
+            false => method.remover(),
+        };
+        let guard = SafetyGuard::new(roots.clone())
+            .with_protected(config.protected.clone())
+            .with_filters(filters);
+        let report = remove_folders(folders, remover.as_ref(), &guard);
+        self.print_report(&report, &roots, method);
+    }
+
+    /// Picks the folders to clean with `--all` or `--select`, or else with a
+    /// prompt. With `all` every folder is selected, as for a plan. Returns
+    /// `None` if nothing should be removed.
+    ///
+    /// Without a terminal on stdin nothing is ever prompted: the selection is
+    /// used as is, and without one the found folders are only listed.
+    fn choose(
+        &self,
+        found_folders: &[FoundFolder],
+        roots: &[PathBuf],
+        all: bool,
+    ) -> Result<Option<Vec<FoundFolder>>, Box<dyn std::error::Error>> {
+        let interactive = std::io::stdin().is_terminal();
+        let theme = dialoguer::theme::ColorfulTheme::default();
+        let listing = self.list.listing(found_folders, roots);
+        let total: DiskUsage = found_folders.iter().map(|f| f.usage).sum();
+        let found = format!(
+            "Found {} folders to clean ({} total)",
+            found_folders.len(),
+            ByteSize(total.allocated_bytes)
+        );
+
+        if all || self.select.is_some() {
+            let selected = found_folders
+                .iter()
+                .filter(|f| self.select.as_ref().is_none_or(|s| s.matches(f)))
+                .cloned()
+                .collect::<Vec<_>>();
+            let usage: DiskUsage = selected.iter().map(|f| f.usage).sum();
+            println!(
+                "Selected {} of {} folders ({})",
+                selected.len(),
+                found_folders.len(),
+                ByteSize(usage.allocated_bytes)
+            );
+            self.list.listing(&selected, roots).print(|_| None);
+            if interactive && !self.yes && !selected.is_empty() {
+                let confirmed = dialoguer::Confirm::with_theme(&theme)
+                    .with_prompt(format!("Clean {} folders?", selected.len()))
+                    .default(false)
+                    .interact()?;
+                if !confirmed {
+                    return Ok(None);
+                }
+            }
+            return Ok(Some(selected));
+        }
+
+        if !interactive {
+            println!("{found}");
+            listing.print(|_| None);
+            println!(
+                "{}",
+                "Not a terminal, pass --all or --select to clean without a prompt".yellow()
+            );
+            return Ok(None);
+        }
+
+        let order = listing.order();
+        let items = order.iter().map(|i| listing.item(*i)).collect::<Vec<_>>();
+        let selected = dialoguer::MultiSelect::with_theme(&theme)
+            .with_prompt(found)
+            .items(items.as_slice())
+            .report(true)
+            .interact()?;
+        Ok(Some(
+            selected
+                .iter()
+                .map(|i| found_folders[order[*i]].clone())
+                .collect(),
+        ))
+    }
+
+    /// Prints how much was found in each of several scanned roots.
+    fn print_roots(roots: &[PathBuf], found_folders: &[FoundFolder]) {
+        for root in roots {
+            let folders = found_folders
+                .iter()
+                .filter(|f| root_of(&f.path, roots) == Some(root))
+                .collect::<Vec<_>>();
+            let usage: DiskUsage = folders.iter().map(|f| f.usage).sum();
+            println!(
+                "{}: {} folders ({})",
+                display_path(root),
+                folders.len(),
+                ByteSize(usage.allocated_bytes)
+            );
+        }
+    }
+
+    fn print_report(&self, report: &RemovalReport, roots: &[PathBuf], method: RemovalMethod) {
+        let outcomes = report.removed().collect::<Vec<_>>();
+        let removed = outcomes.len();
+        match method {
+            _ if self.dry_run => println!(
+                "Would remove {removed} folders, freeing {}",
+                ByteSize(report.bytes_removed())
+            ),
+            RemovalMethod::Delete => println!(
+                "Removed {removed} folders, freed {}",
+                ByteSize(report.bytes_freed())
+            ),
+            RemovalMethod::Trash => println!(
+                "Moved {removed} folders to the trash, {} can be freed by emptying it",
+                ByteSize(report.bytes_freed())
+            ),
+        }
+        let folders = outcomes
+            .iter()
+            .map(|outcome| outcome.folder.clone())
+            .collect::<Vec<_>>();
+        let listing = self.list.listing(&folders, roots);
+        listing
+            .with_color(AnsiColors::Green)
+            .print(|i| match &outcomes[i].result {
+                Ok(Removal::Trashed { location }) => {
+                    Some(format!("→ {}", display_path(location).bright_black()))
+                }
+                _ => None,
+            });
+
+        let failed = report.failed().collect::<Vec<_>>();
+        if !failed.is_empty() {
+            println!("Errored {} folders", failed.len());
+        }
+        for outcome in failed {
+            let Err(error) = &outcome.result else {
+                continue;
+            };
+            println!(
+                "\t- {} ({})",
+                display_path(&outcome.folder.path).red(),
+                error.kind
+            );
+            if error.path != outcome.folder.path {
+                println!("\t  at {}", display_path(&error.path).bright_black());
+            }
+            if outcome.bytes_freed > 0 {
+                println!(
+                    "\t  {} freed before failing",
+                    ByteSize(outcome.bytes_freed).bright_black()
+                );
+            }
+        }
+        if !report.refused.is_empty() {
+            println!("Refused {} folders", report.refused.len());
+            for refusal in &report.refused {
+                println!(
+                    "\t- {} ({})",
+                    display_path(&refusal.folder.path).yellow(),
+                    refusal.violation
+                );
+            }
+        }
+        debug!("Removal took {:?}", report.elapsed);
+    }
+
+    /// What removing does in this run, for confirmation prompts.
+    fn removal_label(&self, config: &Config) -> &'static str {
+        match self.removal_method(config) {
+            _ if self.dry_run => "Dry run",
+            RemovalMethod::Delete => "Delete",
+            RemovalMethod::Trash => "Move to trash",
+        }
+    }
+
+    /// Removal method of this run, `--trash`/`--delete` override the configuration.
+    fn removal_method(&self, config: &Config) -> RemovalMethod {
+        match (self.trash, self.delete) {
+            (true, _) => RemovalMethod::Trash,
+            (_, true) => RemovalMethod::Delete,
+            _ => config.removal,
+        }
+    }
+}
+
+impl ScanArgs {
+    /// The folders given on the command line, or else the configured roots,
+    /// without those nested in others. Folders that do not exist are reported,
+    /// failing for given ones and skipping configured ones. Fails if there are
+    /// none left.
+    pub fn roots(&self, config: &Config) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let given = !self.paths.is_empty();
+        let paths = match given {
+            true => &self.paths,
+            false => &config.roots,
+        };
+        let (roots, missing): (Vec<_>, Vec<_>) = paths.iter().cloned().partition(|p| p.is_dir());
+        for path in &missing {
+            eprintln!("{} {}", "No such folder:".yellow(), path.display());
+        }
+        if given && !missing.is_empty() {
+            return Err("the folders to scan must exist".into());
+        }
+        let roots = dedupe_roots(roots);
+        if roots.is_empty() {
+            return Err(
+                "no folders to scan, pass them or add defaults with `config roots add`".into(),
+            );
+        }
+        Ok(roots)
+    }
+
+    /// Filters of the configuration, with this run's thresholds and options applied.
+    pub fn matcher(&self, config: &Config) -> Result<FilterMatcher, Box<dyn std::error::Error>> {
+        Ok(config
+            .matcher()?
+            .with_min_age(self.older_than.or(config.min_age))
+            .with_min_size(self.min_size.or(config.min_size))
+            .with_cachedir_tags(self.cachedir_tags || config.cachedir_tags)
+            .with_mounts(MountPolicy::new(
+                self.one_file_system || config.same_filesystem,
+                &[config.skipped_fs_types.as_slice(), &self.skip_fs].concat(),
+            )?))
+    }
+
+    /// Scans `roots` while printing progress, returns `None` if interrupted with Ctrl-C.
+    /// Found folders are grouped by root, in the order the roots were given.
+    ///
+    /// With `project_age` the age of every folder covers its project, see
+    /// [`FilterMatcher::with_project_age`].
+    pub fn scan(
+        &self,
+        roots: &[PathBuf],
+        config: &Config,
+        project_age: bool,
+    ) -> Result<Option<Vec<FoundFolder>>, Box<dyn std::error::Error>> {
+        let filters = self.matcher(config)?.with_project_age(project_age);
+        let threads = self.threads.unwrap_or(config.threads);
+
+        let cancel = CancellationToken::new();
+        let interrupt = cancel.clone();
+        ctrlc::set_handler(move || {
+            // A second interrupt, or one after the scan finished, exits right away
+            if interrupt.is_cancelled() {
+                std::process::exit(130);
+            }
+            interrupt.cancel();
+        })?;
+
+        let show_progress = std::io::stderr().is_terminal();
+        let mut found_folders = Vec::new();
+        for event in scan_roots_streaming(roots.to_vec(), filters, threads, cancel.clone()) {
+            match event {
+                ScanEvent::Progress(progress) if show_progress => {
+                    let current = progress.current_path.to_string_lossy();
+                    let current = match current.char_indices().rev().nth(59) {
+                        Some((i, _)) => format!("…{}", &current[i..]),
+                        None => current.to_string(),
+                    };
+                    eprint!(
+                        "\r\x1b[2KScanned {} folders, {} matches {}",
+                        progress.dirs_visited,
+                        progress.matches,
+                        current.bright_black()
+                    );
+                }
+                ScanEvent::Progress(_) => {}
+                ScanEvent::Found(folder) => found_folders.push(folder),
+                ScanEvent::Finished {
+                    progress,
+                    cancelled,
+                } => {
+                    if show_progress {
+                        eprint!("\r\x1b[2K");
+                    }
+                    if cancelled {
+                        return Ok(None);
+                    }
+                    if progress.too_recent > 0 {
+                        eprintln!(
+                            "Skipped {} folders of recently modified projects",
+                            progress.too_recent
+                        );
+                    }
+                    if progress.too_small > 0 {
+                        eprintln!(
+                            "Skipped {} folders below the minimum size",
+                            progress.too_small
+                        );
+                    }
+                    if !progress.skipped_mounts.is_empty() {
+                        eprintln!("Skipped {} mounts", progress.skipped_mounts.len());
+                        for mount in &progress.skipped_mounts {
+                            eprintln!(
+                                "\t- {} ({})",
+                                mount.path.display(),
+                                mount.fs_type.bright_black()
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        cancel.cancel();
+
+        let root_index = |f: &FoundFolder| roots.iter().position(|r| f.path.starts_with(r));
+        found_folders.sort_by(|a, b| (root_index(a), &a.path).cmp(&(root_index(b), &b.path)));
+        Ok(Some(found_folders))
+    }
 }
 
 impl Commands {
@@ -190,6 +811,81 @@ impl Commands {
                     ConfigCommands::List.process(config)?;
                 }
             }
+            Commands::Scan {
+                scan,
+                format,
+                out,
+                list,
+            } => {
+                let roots = scan.roots(config)?;
+                let Some(mut found_folders) = scan.scan(&roots, config, list.uses_age())? else {
+                    eprintln!("Scan cancelled");
+                    return Ok(());
+                };
+                list.sort(&mut found_folders);
+                let table = list.listing(&found_folders, &roots);
+                let report = ScanReport::new(roots, &found_folders);
+                print_scan(&report, *format, &table)?;
+                if let Some(out) = out {
+                    // Only replace an existing plan once the new one is complete
+                    let mut partial = out.clone().into_os_string();
+                    partial.push(".partial");
+                    std::fs::write(&partial, serde_json::to_vec_pretty(&report)?)?;
+                    std::fs::rename(&partial, out)?;
+                    eprintln!(
+                        "Wrote a plan of {} folders to {}, review it and run `clean --plan {}`",
+                        report.folders.len(),
+                        out.display(),
+                        out.display()
+                    );
+                }
+            }
+            Commands::Stats { scan, top } => {
+                let roots = scan.roots(config)?;
+                let Some(found_folders) = scan.scan(&roots, config, false)? else {
+                    eprintln!("Scan cancelled");
+                    return Ok(());
+                };
+                print_stats(&ScanStats::new(&found_folders, &roots), *top);
+            }
+            Commands::Tui { scan, clean } => {
+                if !std::io::stdin().is_terminal() {
+                    return Err(
+                        "the terminal UI needs a terminal, use `scan` or `clean --all`".into(),
+                    );
+                }
+                let roots = scan.roots(config)?;
+                // The terminal UI shows and sorts by age
+                let Some(found_folders) = scan.scan(&roots, config, true)? else {
+                    println!("Scan cancelled");
+                    return Ok(());
+                };
+                let selected = found_folders
+                    .iter()
+                    .map(|f| clean.all || clean.select.as_ref().is_some_and(|s| s.matches(f)))
+                    .collect();
+                let removal = clean.removal_label(config);
+                let Some(chosen) = tui::run(found_folders, selected, removal, clean.list.sort)?
+                else {
+                    return Ok(());
+                };
+                clean.remove(chosen, roots, scan.matcher(config)?, config);
+            }
+            Commands::Explore { path } => {
+                if !std::io::stdin().is_terminal() {
+                    return Err("the explorer needs a terminal, use `scan` instead".into());
+                }
+                eprintln!("Measuring {}…", path.display());
+                let tree = SizeTree::build(path, &config.matcher()?)?;
+                explore::run(tree, config, Config::matcher)?;
+            }
+            Commands::Clean { scan, clean, plan } => {
+                let roots = scan.roots(config)?;
+                match plan {
+                    Some(plan) => clean.clean_plan(plan, scan, roots, config)?,
+                    None => clean.clean(scan, roots, config)?,
+                }
+            }
         }
         Ok(())
     }
@@ -205,6 +901,55 @@ impl ConfigCommands {
                     println!("No filter command provided.");
                 }
             }
+            ConfigCommands::Presets { command } => {
+                command
+                    .as_ref()
+                    .unwrap_or(&PresetCommands::List)
+                    .process(config)?;
+            }
+            ConfigCommands::CachedirTags { enabled } => {
+                debug!("Setting CACHEDIR.TAG matching: {enabled}");
+                config.cachedir_tags = *enabled;
+                config.store()?;
+            }
+            ConfigCommands::SameFilesystem { enabled } => {
+                debug!("Setting same filesystem: {enabled}");
+                config.same_filesystem = *enabled;
+                config.store()?;
+            }
+            ConfigCommands::SkipFs { command } => {
+                command
+                    .as_ref()
+                    .unwrap_or(&SkipFsCommands::List)
+                    .process(config)?;
+            }
+            ConfigCommands::Removal { method } => {
+                debug!("Setting removal method: {method}");
+                config.removal = *method;
+                config.store()?;
+            }
+            ConfigCommands::MinAge { age } => {
+                debug!("Setting minimum age: {age:?}");
+                config.min_age = *age;
+                config.store()?;
+            }
+            ConfigCommands::MinSize { size } => {
+                debug!("Setting minimum size: {size:?}");
+                config.min_size = *size;
+                config.store()?;
+            }
+            ConfigCommands::Roots { command } => {
+                command
+                    .as_ref()
+                    .unwrap_or(&RootCommands::List)
+                    .process(config)?;
+            }
+            ConfigCommands::Protected { command } => {
+                command
+                    .as_ref()
+                    .unwrap_or(&ProtectedCommands::List)
+                    .process(config)?;
+            }
             ConfigCommands::List => {
                 println!("{}", &config);
             }
@@ -225,14 +970,24 @@ impl ConfigCommands {
 impl FilterCommands {
     pub fn process(&self, config: &mut config::Config) -> Result<(), Box<dyn std::error::Error>> {
         match self {
-            FilterCommands::Add { pattern } => {
+            FilterCommands::Add {
+                pattern,
+                markers,
+                older_than,
+                min_size,
+            } => {
                 debug!("Adding pattern: {pattern}");
-                config.filters.push(pattern.clone());
+                config.filters.push(
+                    Filter::new(pattern.clone())
+                        .with_markers(markers.clone())
+                        .with_min_age(*older_than)
+                        .with_min_size(*min_size),
+                );
                 config.store()?;
             }
             FilterCommands::Remove { pattern } => {
                 debug!("Removing pattern: {pattern}");
-                config.filters.retain(|p| p != pattern);
+                config.filters.retain(|f| &f.rule != pattern);
                 config.store()?;
             }
             FilterCommands::List => {
@@ -254,3 +1009,336 @@ impl FilterCommands {
         Ok(())
     }
 }
+
+impl PresetCommands {
+    pub fn process(&self, config: &mut config::Config) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            PresetCommands::Enable { preset } => {
+                debug!("Enabling preset: {preset}");
+                if !config.presets.contains(preset) {
+                    config.presets.push(*preset);
+                }
+                config.store()?;
+            }
+            PresetCommands::Disable { preset } => {
+                debug!("Disabling preset: {preset}");
+                config.presets.retain(|p| p != preset);
+                config.store()?;
+            }
+            PresetCommands::List => {
+                debug!("Listing presets");
+                for preset in Preset::ALL {
+                    let enabled = match config.presets.contains(&preset) {
+                        true => "enabled".green().to_string(),
+                        false => "disabled".bright_black().to_string(),
+                    };
+                    println!("{preset} ({enabled}): {}", preset.description());
+                    for filter in preset.filters() {
+                        println!("\t- {filter}");
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SkipFsCommands {
+    pub fn process(&self, config: &mut config::Config) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            SkipFsCommands::Add { fs_type } => {
+                debug!("Skipping filesystem type: {fs_type}");
+                MountPolicy::validate_fs_type(fs_type)?;
+                if !config.skipped_fs_types.contains(fs_type) {
+                    config.skipped_fs_types.push(fs_type.clone());
+                }
+                config.store()?;
+            }
+            SkipFsCommands::Remove { fs_type } => {
+                debug!("No longer skipping filesystem type: {fs_type}");
+                config.skipped_fs_types.retain(|t| t != fs_type);
+                config.store()?;
+            }
+            SkipFsCommands::List => {
+                debug!("Listing skipped filesystem types");
+                for fs_type in &config.skipped_fs_types {
+                    println!("\t- {fs_type}");
+                }
+            }
+            SkipFsCommands::Reset => {
+                debug!("Resetting skipped filesystem types");
+                config.skipped_fs_types = config::default_skipped_fs_types();
+                config.store()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RootCommands {
+    pub fn process(&self, config: &mut config::Config) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            RootCommands::Add { path } => {
+                let path = std::path::absolute(path)?;
+                debug!("Adding root: {}", path.display());
+                if !config.roots.contains(&path) {
+                    config.roots.push(path);
+                }
+                config.store()?;
+            }
+            RootCommands::Remove { path } => {
+                let path = std::path::absolute(path)?;
+                debug!("Removing root: {}", path.display());
+                config.roots.retain(|p| p != &path);
+                config.store()?;
+            }
+            RootCommands::List => {
+                debug!("Listing roots");
+                for path in &config.roots {
+                    println!("\t- {}", path.display());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ProtectedCommands {
+    pub fn process(&self, config: &mut config::Config) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            ProtectedCommands::Add { path } => {
+                let path = std::path::absolute(path)?;
+                debug!("Protecting path: {}", path.display());
+                if !config.protected.contains(&path) {
+                    config.protected.push(path);
+                }
+                config.store()?;
+            }
+            ProtectedCommands::Remove { path } => {
+                let path = std::path::absolute(path)?;
+                debug!("Unprotecting path: {}", path.display());
+                config.protected.retain(|p| p != &path);
+                config.store()?;
+            }
+            ProtectedCommands::List => {
+                debug!("Listing protected paths");
+                for path in &config.protected {
+                    println!("\t- {}", path.display());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Prints the results of the `scan` command to stdout in `format`, laid out
+/// as `table` for the table format.
+fn print_scan(
+    report: &ScanReport,
+    format: OutputFormat,
+    table: &Listing,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut out = std::io::stdout().lock();
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, report)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Ndjson => {
+            for record in &report.folders {
+                serde_json::to_writer(&mut out, record)?;
+                writeln!(out)?;
+            }
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            for record in &report.folders {
+                writer.serialize(record)?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Table => {
+            table.write_table(&mut out)?;
+            let total: u64 = report.folders.iter().map(|record| record.size).sum();
+            writeln!(
+                out,
+                "{} folders, {} total",
+                report.folders.len(),
+                ByteSize(total)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints every breakdown of `stats` cut to its `top` largest entries, with
+/// bars showing their share of the total.
+fn print_stats(stats: &ScanStats, top: usize) {
+    const BAR_WIDTH: usize = 30;
+
+    let styled = std::io::stdout().is_terminal();
+    let bold = |text: &str| paint(text, Style::new().bold(), styled);
+    let total = stats.total.allocated_bytes;
+    println!(
+        "{} reclaimable in {} folders",
+        bold(&ByteSize(total).to_string()),
+        stats.folders
+    );
+    if stats.folders == 0 {
+        return;
+    }
+
+    let section = |title: &str, rows: Vec<(String, u64, String)>| {
+        println!();
+        match rows.len() > top {
+            true => println!("{} (top {top} of {})", bold(title), rows.len()),
+            false => println!("{}", bold(title)),
+        }
+        let width = rows
+            .iter()
+            .take(top)
+            .map(|(name, ..)| name.chars().count())
+            .max()
+            .unwrap_or_default();
+        for (name, bytes, note) in rows.iter().take(top) {
+            let filled = (*bytes as u128 * BAR_WIDTH as u128 / total.max(1) as u128) as usize;
+            println!(
+                "  {name:<width$}  {:>10}  {:>5.1}% [{}{}]  {}",
+                ByteSize(*bytes).to_string(),
+                *bytes as f64 * 100.0 / total.max(1) as f64,
+                "#".repeat(filled),
+                " ".repeat(BAR_WIDTH - filled),
+                paint(note, Style::new().bright_black(), styled)
+            );
+        }
+    };
+    section(
+        "By ecosystem",
+        bucket_rows(&stats.by_ecosystem, |key| key.to_string()),
+    );
+    section("By rule", bucket_rows(&stats.by_rule, String::clone));
+    section(
+        "By root",
+        bucket_rows(&stats.by_root, |key| display_path(key)),
+    );
+    section(
+        "By project",
+        bucket_rows(&stats.by_project, |key| display_path(key)),
+    );
+    section(
+        "Largest folders",
+        stats
+            .largest
+            .iter()
+            .map(|folder| {
+                (
+                    display_path(&folder.path),
+                    folder.usage.allocated_bytes,
+                    folder.reason.to_string(),
+                )
+            })
+            .collect(),
+    );
+}
+
+/// Rows of [`print_stats`] for `buckets`, labelled with `name`.
+fn bucket_rows<K>(
+    buckets: &[Bucket<K>],
+    name: impl Fn(&K) -> String,
+) -> Vec<(String, u64, String)> {
+    buckets
+        .iter()
+        .map(|bucket| {
+            (
+                name(&bucket.key),
+                bucket.usage.allocated_bytes,
+                format!("{} folders", bucket.folders),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dev_cleaner_core::folderscan::scan_folders;
+    use std::fs;
+
+    /// Creates a Rust project below `dir` and returns its `target` folder.
+    fn project(dir: &Path) -> PathBuf {
+        fs::create_dir_all(dir.join("proj/target/debug")).unwrap();
+        fs::write(dir.join("proj/Cargo.toml"), "[package]").unwrap();
+        dir.join("proj/target")
+    }
+
+    #[test]
+    fn edited_plan_cannot_widen_the_roots() {
+        let allowed = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let inside_target = project(allowed.path());
+        let outside_target = project(outside.path());
+
+        // A plan edited to also cover a folder that was never meant to be scanned
+        let filters = Config::default().filters.compile().unwrap();
+        let mut found = scan_folders(allowed.path(), &filters);
+        found.extend(scan_folders(outside.path(), &filters));
+        let roots = vec![allowed.path().to_path_buf(), outside.path().to_path_buf()];
+        let plan = allowed.path().join("plan.json");
+        fs::write(
+            &plan,
+            serde_json::to_vec(&ScanReport::new(roots, &found)).unwrap(),
+        )
+        .unwrap();
+
+        let mut config = Config {
+            roots: vec![allowed.path().to_path_buf()],
+            ..Config::default()
+        };
+        let cli = DevCleanerCli::try_parse_from([
+            "dev_cleaner".as_ref(),
+            "clean".as_ref(),
+            "--plan".as_ref(),
+            plan.as_os_str(),
+            "--delete".as_ref(),
+            "--yes".as_ref(),
+        ])
+        .unwrap();
+        cli.process(&mut config).unwrap();
+
+        assert!(!inside_target.exists());
+        assert!(outside_target.join("debug").exists());
+    }
+
+    #[test]
+    fn plan_refuses_folders_excluded_since() {
+        let root = tempfile::tempdir().unwrap();
+        let target = project(root.path());
+        let filters = Config::default().filters.compile().unwrap();
+        let found = scan_folders(root.path(), &filters);
+        let plan = root.path().join("plan.json");
+        let report = ScanReport::new(vec![root.path().to_path_buf()], &found);
+        fs::write(&plan, serde_json::to_vec(&report).unwrap()).unwrap();
+
+        // The folder turned out to be checked in after the plan was written
+        fs::write(root.path().join("proj/.devcleanerignore"), "target\n").unwrap();
+        let record = &report.folders[0];
+        assert_eq!(record.verify(&filters), Err(Violation::Excluded));
+
+        let mut config = Config {
+            roots: vec![root.path().to_path_buf()],
+            ..Config::default()
+        };
+        let cli = DevCleanerCli::try_parse_from([
+            "dev_cleaner".as_ref(),
+            "clean".as_ref(),
+            "--plan".as_ref(),
+            plan.as_os_str(),
+            "--delete".as_ref(),
+            "--yes".as_ref(),
+        ])
+        .unwrap();
+        cli.process(&mut config).unwrap();
+
+        assert!(target.join("debug").exists());
+    }
+}
@@ -0,0 +1,420 @@
+//! Sorting, grouping and columns of the found folders printed by the CLI.
+
+use std::{
+    cmp::Ordering,
+    env,
+    fmt::Display,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, ValueEnum};
+use dev_cleaner_core::{
+    folderscan::{FoundFolder, root_of},
+    presets::Preset,
+    usage::{ByteSize, DiskUsage},
+};
+use owo_colors::{AnsiColors, OwoColorize, Style};
+
+/// Order of listed folders.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// Largest first
+    Size,
+    /// Least recently modified first
+    Age,
+    /// Alphabetically
+    #[default]
+    Path,
+    /// By the rule that matched, then alphabetically
+    Rule,
+    /// By the preset providing the rule, then alphabetically
+    Ecosystem,
+}
+
+impl SortKey {
+    /// Natural order of the key: paths ascending, the largest and oldest first.
+    pub fn compare(self, a: &FoundFolder, b: &FoundFolder) -> Ordering {
+        let key = match self {
+            SortKey::Path => Ordering::Equal,
+            SortKey::Size => b.usage.allocated_bytes.cmp(&a.usage.allocated_bytes),
+            SortKey::Age => a.last_modified().cmp(&b.last_modified()),
+            SortKey::Rule => a.reason.to_string().cmp(&b.reason.to_string()),
+            SortKey::Ecosystem => Preset::ecosystem(&a.reason).cmp(Preset::ecosystem(&b.reason)),
+        };
+        key.then_with(|| a.path.cmp(&b.path))
+    }
+
+    /// The key after this one, for cycling through all of them.
+    pub fn next(self) -> SortKey {
+        match self {
+            SortKey::Path => SortKey::Size,
+            SortKey::Size => SortKey::Age,
+            SortKey::Age => SortKey::Rule,
+            SortKey::Rule => SortKey::Ecosystem,
+            SortKey::Ecosystem => SortKey::Path,
+        }
+    }
+
+    /// The key as written on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            SortKey::Path => "path",
+            SortKey::Size => "size",
+            SortKey::Age => "age",
+            SortKey::Rule => "rule",
+            SortKey::Ecosystem => "ecosystem",
+        }
+    }
+}
+
+/// Headings listed folders are grouped under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    /// The folder containing the match
+    Project,
+    /// The rule that matched
+    Rule,
+    /// The scanned folder it was found in
+    Root,
+}
+
+/// A column of listed folders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Column {
+    /// Home-relative path, or relative to the group heading
+    Path,
+    /// Bytes allocated on disk
+    Size,
+    /// Number of files
+    Files,
+    /// Time since the folder or its project was last modified
+    Age,
+    /// Date of the last modification
+    Modified,
+    /// The rule that matched
+    Rule,
+    /// The preset providing the rule
+    Ecosystem,
+}
+
+impl Column {
+    fn title(self) -> &'static str {
+        match self {
+            Column::Path => "PATH",
+            Column::Size => "SIZE",
+            Column::Files => "FILES",
+            Column::Age => "AGE",
+            Column::Modified => "MODIFIED",
+            Column::Rule => "RULE",
+            Column::Ecosystem => "ECOSYSTEM",
+        }
+    }
+
+    fn right_aligned(self) -> bool {
+        matches!(self, Column::Size | Column::Files | Column::Age)
+    }
+}
+
+/// How found folders are listed, in prompts as well as in summaries.
+#[derive(Debug, Clone, Args)]
+pub struct ListArgs {
+    /// Order of listed folders
+    #[arg(long, value_enum, default_value_t)]
+    pub sort: SortKey,
+
+    /// List folders under a heading per project, rule or scanned root
+    #[arg(long, value_enum, value_name = "GROUP")]
+    pub group_by: Option<GroupBy>,
+
+    /// Columns of listed folders, comma separated
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [Column::Path, Column::Size, Column::Files, Column::Rule]
+    )]
+    pub columns: Vec<Column>,
+}
+
+impl ListArgs {
+    /// Sorts `folders` by `--sort`.
+    pub fn sort(&self, folders: &mut [FoundFolder]) {
+        folders.sort_by(|a, b| self.sort.compare(a, b));
+    }
+
+    /// Returns `true` if the listing shows or sorts by the age of folders.
+    pub fn uses_age(&self) -> bool {
+        self.sort == SortKey::Age
+            || self
+                .columns
+                .iter()
+                .any(|column| matches!(column, Column::Age | Column::Modified))
+    }
+
+    /// Lays out `folders`, found below `roots`, for printing.
+    pub fn listing(&self, folders: &[FoundFolder], roots: &[PathBuf]) -> Listing {
+        let mut order = (0..folders.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| self.sort.compare(&folders[*a], &folders[*b]));
+
+        // Groups keep the order of their first folder.
+        let mut groups: Vec<Group> = Vec::new();
+        for i in order {
+            let folder = &folders[i];
+            let name = match self.group_by {
+                None => None,
+                Some(GroupBy::Project) => folder.project_root().map(display_path),
+                Some(GroupBy::Root) => root_of(&folder.path, roots).map(|root| display_path(root)),
+                Some(GroupBy::Rule) => Some(folder.reason.to_string()),
+            };
+            match groups.iter_mut().find(|group| group.name == name) {
+                Some(group) => {
+                    group.folders.push(i);
+                    group.usage += folder.usage;
+                }
+                None => groups.push(Group {
+                    name,
+                    folders: vec![i],
+                    usage: folder.usage,
+                }),
+            }
+        }
+
+        let cells = folders
+            .iter()
+            .map(|folder| {
+                self.columns
+                    .iter()
+                    .map(|column| self.cell(*column, folder, roots))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut group_of = vec![0; folders.len()];
+        for (g, group) in groups.iter().enumerate() {
+            for i in &group.folders {
+                group_of[*i] = g;
+            }
+        }
+        let name_width = groups
+            .iter()
+            .filter_map(|group| Some(group.name.as_ref()?.chars().count()))
+            .max()
+            .unwrap_or_default();
+
+        let mut widths = self
+            .columns
+            .iter()
+            .map(|column| column.title().len())
+            .collect::<Vec<_>>();
+        for row in &cells {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        Listing {
+            columns: self.columns.clone(),
+            groups,
+            group_of,
+            name_width,
+            cells,
+            widths,
+            color: None,
+            styled: io::stdout().is_terminal(),
+        }
+    }
+
+    fn cell(&self, column: Column, folder: &FoundFolder, roots: &[PathBuf]) -> String {
+        match column {
+            Column::Path => {
+                let base = match self.group_by {
+                    Some(GroupBy::Project) => folder.project_root(),
+                    Some(GroupBy::Root) => root_of(&folder.path, roots).map(PathBuf::as_path),
+                    _ => None,
+                };
+                match base.and_then(|base| folder.path.strip_prefix(base).ok()) {
+                    Some(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+                    Some(relative) => relative.display().to_string(),
+                    None => display_path(&folder.path),
+                }
+            }
+            Column::Size => ByteSize(folder.usage.allocated_bytes).to_string(),
+            Column::Files => folder.usage.file_count.to_string(),
+            Column::Age => folder
+                .age()
+                .map_or_else(|| "-".to_string(), |age| age.rounded().to_string()),
+            Column::Modified => folder
+                .last_modified()
+                .and_then(|time| jiff::Timestamp::try_from(time).ok())
+                .map_or_else(
+                    || "-".to_string(),
+                    |time| {
+                        time.to_zoned(jiff::tz::TimeZone::system())
+                            .strftime("%Y-%m-%d %H:%M")
+                            .to_string()
+                    },
+                ),
+            Column::Rule => folder.reason.to_string(),
+            Column::Ecosystem => Preset::ecosystem(&folder.reason).to_string(),
+        }
+    }
+}
+
+struct Group {
+    /// Project, root or rule the folders share, `None` without grouping.
+    name: Option<String>,
+    /// Indices of the folders, sorted.
+    folders: Vec<usize>,
+    usage: DiskUsage,
+}
+
+impl Group {
+    fn heading(&self) -> Option<String> {
+        Some(format!(
+            "{} ({} folders, {})",
+            self.name.as_ref()?,
+            self.folders.len(),
+            ByteSize(self.usage.allocated_bytes)
+        ))
+    }
+}
+
+/// Found folders laid out in aligned columns, see [`ListArgs::listing`].
+pub struct Listing {
+    columns: Vec<Column>,
+    groups: Vec<Group>,
+    /// Index of the group of every folder.
+    group_of: Vec<usize>,
+    /// Width of the longest group name.
+    name_width: usize,
+    /// Cells of every folder, in the order of `columns`.
+    cells: Vec<Vec<String>>,
+    widths: Vec<usize>,
+    color: Option<AnsiColors>,
+    /// Whether to style the output, only done on a terminal.
+    styled: bool,
+}
+
+impl Listing {
+    /// Colors the path column, e.g. to tell removed folders from failed ones.
+    pub fn with_color(mut self, color: AnsiColors) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Folder indices in the listed order, grouped and sorted.
+    pub fn order(&self) -> Vec<usize> {
+        self.groups
+            .iter()
+            .flat_map(|group| group.folders.iter().copied())
+            .collect()
+    }
+
+    /// The line of folder `i`, prefixed with its group for flat lists such as prompts.
+    pub fn item(&self, i: usize) -> String {
+        let Some(name) = &self.groups[self.group_of[i]].name else {
+            return self.line(i);
+        };
+        let name = format!("{name:<width$}", width = self.name_width);
+        format!(
+            "{}  {}",
+            paint(name, Style::new().bright_black(), self.styled),
+            self.line(i)
+        )
+    }
+
+    /// The aligned columns of folder `i`.
+    pub fn line(&self, i: usize) -> String {
+        let last = self.columns.len().saturating_sub(1);
+        let cells = self.columns.iter().zip(&self.cells[i]).zip(&self.widths);
+        let mut line = Vec::new();
+        for (j, ((column, cell), width)) in cells.enumerate() {
+            let padded = match (j == last, column.right_aligned()) {
+                (true, false) => cell.clone(),
+                (_, true) => format!("{cell:>width$}"),
+                (false, false) => format!("{cell:<width$}"),
+            };
+            line.push(match column {
+                Column::Path => match self.color {
+                    Some(color) => paint(padded, Style::new().color(color), self.styled),
+                    None => padded,
+                },
+                Column::Rule | Column::Ecosystem => {
+                    paint(padded, Style::new().bright_black(), self.styled)
+                }
+                _ => padded,
+            });
+        }
+        line.join("  ")
+    }
+
+    /// Prints every folder as a list item under its group heading, followed
+    /// by what `note` returns for it.
+    pub fn print(&self, note: impl Fn(usize) -> Option<String>) {
+        for group in &self.groups {
+            let indent = match group.heading() {
+                Some(heading) => {
+                    println!("\t{}", paint(heading, Style::new().bold(), self.styled));
+                    "\t\t"
+                }
+                None => "\t",
+            };
+            for i in &group.folders {
+                match note(*i) {
+                    Some(note) => println!("{indent}- {} {note}", self.line(*i)),
+                    None => println!("{indent}- {}", self.line(*i)),
+                }
+            }
+        }
+    }
+
+    /// Writes the folders as a table with a header row.
+    pub fn write_table(&self, out: &mut impl Write) -> io::Result<()> {
+        let last = self.columns.len().saturating_sub(1);
+        let header = self
+            .columns
+            .iter()
+            .zip(&self.widths)
+            .enumerate()
+            .map(
+                |(j, (column, width))| match (j == last, column.right_aligned()) {
+                    (true, false) => column.title().to_string(),
+                    (_, true) => format!("{:>width$}", column.title()),
+                    (false, false) => format!("{:<width$}", column.title()),
+                },
+            )
+            .collect::<Vec<_>>()
+            .join("  ");
+        writeln!(out, "{}", paint(header, Style::new().bold(), self.styled))?;
+        for group in &self.groups {
+            if let Some(heading) = group.heading() {
+                writeln!(out, "{}", paint(heading, Style::new().bold(), self.styled))?;
+            }
+            for i in &group.folders {
+                writeln!(out, "{}", self.line(*i))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `text` with `style` applied if `styled`, so that piped output stays plain.
+pub fn paint(text: impl Display, style: Style, styled: bool) -> String {
+    match styled {
+        true => text.style(style).to_string(),
+        false => text.to_string(),
+    }
+}
+
+/// `path` with the home folder shortened to `~`.
+pub fn display_path(path: &Path) -> String {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    match home
+        .as_deref()
+        .and_then(|home| path.strip_prefix(home).ok())
+    {
+        Some(relative) if relative.as_os_str().is_empty() => "~".to_string(),
+        Some(relative) => format!("~/{}", relative.display()),
+        None => path.display().to_string(),
+    }
+}
@@ -0,0 +1,542 @@
+//! Full-screen terminal UI for choosing among found folders, see [`run`].
+
+use std::{fs, io, path::PathBuf};
+
+use dev_cleaner_core::{
+    folderscan::FoundFolder,
+    presets::Preset,
+    usage::{ByteSize, DiskUsage},
+};
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
+};
+
+use crate::listing::SortKey;
+
+const HELP: &str = "↑↓ move  space toggle  a all  r same rule  s sort  S reverse  t tree  / search  enter clean  q quit";
+
+/// A line of the results table.
+#[derive(Debug, Clone)]
+enum Entry {
+    /// A project root with the indices of its found folders, in tree mode.
+    Project { root: PathBuf, folders: Vec<usize> },
+    /// The index of a found folder.
+    Folder(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Screen {
+    Browse,
+    Search,
+    Confirm,
+}
+
+struct App {
+    folders: Vec<FoundFolder>,
+    selected: Vec<bool>,
+    /// What confirming does, e.g. `Move to trash`.
+    removal: &'static str,
+    sort: SortKey,
+    reversed: bool,
+    tree: bool,
+    query: String,
+    screen: Screen,
+    entries: Vec<Entry>,
+    state: TableState,
+    /// Top-level contents of the folder shown in the details pane.
+    contents: Option<(PathBuf, Vec<String>)>,
+}
+
+/// Lets the user browse `folders` and returns those chosen for removal, or
+/// `None` if the UI was left without confirming. `selected` holds the initial
+/// selection, `removal` names what confirming does and `sort` is the initial order.
+pub fn run(
+    folders: Vec<FoundFolder>,
+    selected: Vec<bool>,
+    removal: &'static str,
+    sort: SortKey,
+) -> io::Result<Option<Vec<FoundFolder>>> {
+    let mut app = App {
+        folders,
+        selected,
+        removal,
+        sort,
+        reversed: false,
+        tree: false,
+        query: String::new(),
+        screen: Screen::Browse,
+        entries: Vec::new(),
+        state: TableState::default().with_selected(0),
+        contents: None,
+    };
+    app.rebuild();
+
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<Option<Vec<FoundFolder>>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                return Ok(None);
+            }
+
+            match (self.screen, key.code) {
+                (Screen::Search, KeyCode::Esc) => {
+                    self.query.clear();
+                    self.screen = Screen::Browse;
+                    self.rebuild();
+                }
+                (Screen::Search, KeyCode::Enter) => self.screen = Screen::Browse,
+                (Screen::Search, KeyCode::Backspace) => {
+                    self.query.pop();
+                    self.rebuild();
+                }
+                (Screen::Search, KeyCode::Char(c)) => {
+                    self.query.push(c);
+                    self.rebuild();
+                }
+                (Screen::Confirm, KeyCode::Char('y') | KeyCode::Enter) => {
+                    return Ok(Some(self.chosen()));
+                }
+                (Screen::Confirm, KeyCode::Char('n' | 'q') | KeyCode::Esc) => {
+                    self.screen = Screen::Browse;
+                }
+                (Screen::Browse, KeyCode::Char('q') | KeyCode::Esc) => return Ok(None),
+                (Screen::Search | Screen::Browse, KeyCode::Up) => self.move_by(-1),
+                (Screen::Search | Screen::Browse, KeyCode::Down) => self.move_by(1),
+                (Screen::Browse, KeyCode::Char('k')) => self.move_by(-1),
+                (Screen::Browse, KeyCode::Char('j')) => self.move_by(1),
+                (Screen::Browse, KeyCode::PageUp) => self.move_by(-20),
+                (Screen::Browse, KeyCode::PageDown) => self.move_by(20),
+                (Screen::Browse, KeyCode::Home) => self.state.select_first(),
+                (Screen::Browse, KeyCode::End) => self.move_by(isize::MAX),
+                (Screen::Browse, KeyCode::Char(' ')) => self.toggle_current(),
+                (Screen::Browse, KeyCode::Char('a')) => self.toggle_visible(),
+                (Screen::Browse, KeyCode::Char('r')) => self.toggle_rule(),
+                (Screen::Browse, KeyCode::Char('s')) => {
+                    self.sort = self.sort.next();
+                    self.reversed = false;
+                    self.rebuild();
+                }
+                (Screen::Browse, KeyCode::Char('S')) => {
+                    self.reversed = !self.reversed;
+                    self.rebuild();
+                }
+                (Screen::Browse, KeyCode::Char('t')) => {
+                    self.tree = !self.tree;
+                    self.rebuild();
+                }
+                (Screen::Browse, KeyCode::Char('/')) => self.screen = Screen::Search,
+                (Screen::Browse, KeyCode::Enter | KeyCode::Char('c'))
+                    if self.selected.contains(&true) =>
+                {
+                    self.screen = Screen::Confirm;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The folders selected for removal, in their original order.
+    fn chosen(&self) -> Vec<FoundFolder> {
+        self.folders
+            .iter()
+            .zip(&self.selected)
+            .filter(|(_, selected)| **selected)
+            .map(|(folder, _)| folder.clone())
+            .collect()
+    }
+
+    /// Recomputes the visible entries after the search, sort or grouping changed.
+    fn rebuild(&mut self) {
+        let query = self.query.to_lowercase();
+        let mut visible = (0..self.folders.len())
+            .filter(|i| {
+                let path = self.folders[*i].path.to_string_lossy().to_lowercase();
+                query.is_empty() || path.contains(&query)
+            })
+            .collect::<Vec<_>>();
+        visible.sort_by(|a, b| {
+            let ordering = self.sort.compare(&self.folders[*a], &self.folders[*b]);
+            match self.reversed {
+                true => ordering.reverse(),
+                false => ordering,
+            }
+        });
+
+        self.entries = match self.tree {
+            false => visible.into_iter().map(Entry::Folder).collect(),
+            true => {
+                // Projects appear in the order of their first folder
+                let mut projects: Vec<(PathBuf, Vec<usize>)> = Vec::new();
+                for i in visible {
+                    let root = self.folders[i]
+                        .project_root()
+                        .map(PathBuf::from)
+                        .unwrap_or_default();
+                    match projects.iter_mut().find(|(r, _)| *r == root) {
+                        Some((_, folders)) => folders.push(i),
+                        None => projects.push((root, vec![i])),
+                    }
+                }
+                projects
+                    .into_iter()
+                    .flat_map(|(root, folders)| {
+                        let children = folders.clone().into_iter().map(Entry::Folder);
+                        std::iter::once(Entry::Project { root, folders }).chain(children)
+                    })
+                    .collect()
+            }
+        };
+
+        let last = self.entries.len().saturating_sub(1);
+        self.state
+            .select(Some(self.state.selected().unwrap_or(0).min(last)));
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let last = self.entries.len().saturating_sub(1);
+        let current = self.state.selected().unwrap_or(0);
+        self.state
+            .select(Some(current.saturating_add_signed(delta).min(last)));
+    }
+
+    /// Folder indices of the entry under the cursor.
+    fn current(&self) -> Vec<usize> {
+        match self.state.selected().and_then(|i| self.entries.get(i)) {
+            Some(Entry::Project { folders, .. }) => folders.clone(),
+            Some(Entry::Folder(i)) => vec![*i],
+            None => Vec::new(),
+        }
+    }
+
+    /// Selects all of `folders`, or clears them if they all were selected.
+    fn toggle(&mut self, folders: &[usize]) {
+        let select = !folders.iter().all(|i| self.selected[*i]);
+        for i in folders {
+            self.selected[*i] = select;
+        }
+    }
+
+    fn toggle_current(&mut self) {
+        self.toggle(&self.current());
+    }
+
+    fn visible(&self) -> Vec<usize> {
+        self.entries
+            .iter()
+            .filter_map(|entry| match entry {
+                Entry::Folder(i) => Some(*i),
+                Entry::Project { .. } => None,
+            })
+            .collect()
+    }
+
+    fn toggle_visible(&mut self) {
+        self.toggle(&self.visible());
+    }
+
+    /// Toggles all visible folders matched by the same rule as the current one.
+    fn toggle_rule(&mut self) {
+        let Some(current) = self.current().first().copied() else {
+            return;
+        };
+        let rule = &self.folders[current].reason;
+        let folders = self
+            .visible()
+            .into_iter()
+            .filter(|i| self.folders[*i].reason == *rule)
+            .collect::<Vec<_>>();
+        self.toggle(&folders);
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [results, details] =
+            Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)])
+                .areas(body);
+
+        let chosen: DiskUsage = self.chosen().iter().map(|f| f.usage).sum();
+        let order = match self.reversed {
+            true => "↑",
+            false => "↓",
+        };
+        frame.render_widget(
+            Line::from(format!(
+                " {} · {} folders, {} selected ({}) · sorted by {}{order}{}",
+                dev_cleaner_core::APP_NAME,
+                self.folders.len(),
+                self.selected.iter().filter(|s| **s).count(),
+                ByteSize(chosen.allocated_bytes),
+                self.sort.name(),
+                match self.tree {
+                    true => " · by project",
+                    false => "",
+                }
+            ))
+            .bold(),
+            header,
+        );
+
+        self.draw_results(frame, results);
+        self.draw_details(frame, details);
+
+        let footer_line = match self.screen {
+            Screen::Search => Line::from(format!("/{}█", self.query)),
+            _ if !self.query.is_empty() => {
+                Line::from(format!("filter: {} · {HELP}", self.query)).fg(Color::DarkGray)
+            }
+            _ => Line::from(HELP).fg(Color::DarkGray),
+        };
+        frame.render_widget(footer_line, footer);
+
+        if self.screen == Screen::Confirm {
+            self.draw_confirm(frame);
+        }
+    }
+
+    fn draw_results(&mut self, frame: &mut Frame, area: Rect) {
+        let mark = |folders: &[usize]| {
+            let count = folders.iter().filter(|i| self.selected[**i]).count();
+            match count {
+                0 => "[ ]",
+                n if n == folders.len() => "[x]",
+                _ => "[-]",
+            }
+        };
+        let age = |folder: &FoundFolder| {
+            folder
+                .age()
+                .map(|age| age.rounded().to_string())
+                .unwrap_or_else(|| "-".to_string())
+        };
+
+        let rows = self.entries.iter().map(|entry| match entry {
+            Entry::Project { root, folders } => {
+                let usage: DiskUsage = folders.iter().map(|i| self.folders[*i].usage).sum();
+                let newest = folders
+                    .iter()
+                    .map(|i| &self.folders[*i])
+                    .max_by_key(|f| f.last_modified())
+                    .map(age)
+                    .unwrap_or_default();
+                Row::new([
+                    Cell::from(mark(folders)),
+                    Cell::from(root.display().to_string()).bold(),
+                    Cell::from(ByteSize(usage.allocated_bytes).to_string()),
+                    Cell::from(newest),
+                    Cell::from(""),
+                ])
+            }
+            Entry::Folder(i) => {
+                let folder = &self.folders[*i];
+                let path = match self.tree {
+                    true => format!(
+                        "  └ {}",
+                        folder
+                            .path
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                    ),
+                    false => folder.path.display().to_string(),
+                };
+                let row = Row::new([
+                    Cell::from(mark(&[*i])),
+                    Cell::from(path),
+                    Cell::from(ByteSize(folder.usage.allocated_bytes).to_string()),
+                    Cell::from(age(folder)),
+                    Cell::from(Preset::ecosystem(&folder.reason)),
+                ]);
+                match self.selected[*i] {
+                    true => row.fg(Color::Green),
+                    false => row,
+                }
+            }
+        });
+
+        let column = |key: SortKey, title: &str| match self.sort == key {
+            true => format!("{title}*"),
+            false => title.to_string(),
+        };
+        let header = Row::new([
+            String::new(),
+            column(SortKey::Path, "Path"),
+            column(SortKey::Size, "Size"),
+            column(SortKey::Age, "Age"),
+            column(SortKey::Ecosystem, "Ecosystem"),
+        ])
+        .bold();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(3),
+                Constraint::Fill(1),
+                Constraint::Length(10),
+                Constraint::Length(5),
+                Constraint::Length(10),
+            ],
+        )
+        .header(header)
+        .block(Block::bordered().title(" Results "))
+        .row_highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(table, area, &mut self.state);
+    }
+
+    fn draw_details(&mut self, frame: &mut Frame, area: Rect) {
+        let entry = self
+            .state
+            .selected()
+            .and_then(|i| self.entries.get(i))
+            .cloned();
+        let lines = match entry {
+            Some(Entry::Project { root, folders }) => {
+                let usage: DiskUsage = folders.iter().map(|i| self.folders[*i].usage).sum();
+                let mut lines = vec![
+                    Line::from(root.display().to_string()).bold(),
+                    Line::from(format!(
+                        "{} folders, {}",
+                        folders.len(),
+                        ByteSize(usage.allocated_bytes)
+                    )),
+                    Line::from(""),
+                ];
+                lines.extend(folders.iter().map(|i| {
+                    let folder = &self.folders[*i];
+                    Line::from(format!(
+                        "{} ({})",
+                        folder
+                            .path
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy(),
+                        ByteSize(folder.usage.allocated_bytes)
+                    ))
+                }));
+                lines
+            }
+            Some(Entry::Folder(i)) => {
+                let folder = self.folders[i].clone();
+                let modified = folder
+                    .age()
+                    .map(|age| format!("{} ago", age.rounded()))
+                    .unwrap_or_else(|| "unknown".to_string());
+                let mut lines = vec![
+                    Line::from(folder.path.display().to_string()).bold(),
+                    Line::from(format!("Rule: {}", folder.reason)),
+                    Line::from(format!("Ecosystem: {}", Preset::ecosystem(&folder.reason))),
+                    Line::from(format!(
+                        "Size: {} ({} apparent)",
+                        ByteSize(folder.usage.allocated_bytes),
+                        ByteSize(folder.usage.apparent_bytes)
+                    )),
+                    Line::from(format!("Files: {}", folder.usage.file_count)),
+                    Line::from(format!("Modified: {modified}")),
+                    Line::from(""),
+                    Line::from("Contents:").bold(),
+                ];
+                lines.extend(
+                    self.contents(&folder.path)
+                        .iter()
+                        .map(|line| Line::from(line.as_str())),
+                );
+                lines
+            }
+            None => vec![Line::from("No folders found")],
+        };
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title(" Details ")),
+            area,
+        );
+    }
+
+    /// Top-level entries of `path`, directories first, read once per folder.
+    fn contents(&mut self, path: &PathBuf) -> &[String] {
+        if self.contents.as_ref().is_none_or(|(p, _)| p != path) {
+            let mut entries = fs::read_dir(path)
+                .map(|entries| {
+                    entries
+                        .filter_map(Result::ok)
+                        .map(|entry| {
+                            let name = entry.file_name().to_string_lossy().to_string();
+                            match entry.file_type() {
+                                Ok(t) if t.is_dir() => (false, format!("{name}/")),
+                                _ => {
+                                    let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                                    (true, format!("{name} ({})", ByteSize(size)))
+                                }
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            entries.sort();
+            let entries = entries.into_iter().map(|(_, line)| line).collect();
+            self.contents = Some((path.clone(), entries));
+        }
+        self.contents
+            .as_ref()
+            .map(|(_, entries)| entries.as_slice())
+            .unwrap_or_default()
+    }
+
+    fn draw_confirm(&self, frame: &mut Frame) {
+        let chosen = self.chosen();
+        let usage: DiskUsage = chosen.iter().map(|f| f.usage).sum();
+        let area = frame.area();
+        let [area] = Layout::horizontal([Constraint::Percentage(70)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let mut lines = vec![
+            Line::from(format!(
+                "{}: {} folders, {} to free",
+                self.removal,
+                chosen.len(),
+                ByteSize(usage.allocated_bytes)
+            ))
+            .bold(),
+            Line::from("[y] confirm   [n] back").fg(Color::DarkGray),
+            Line::from(""),
+        ];
+        lines.extend(chosen.iter().map(|folder| {
+            Line::from(format!(
+                "{} ({})",
+                folder.path.display(),
+                ByteSize(folder.usage.allocated_bytes)
+            ))
+        }));
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Confirm ").fg(Color::Yellow)),
+            area,
+        );
+    }
+}
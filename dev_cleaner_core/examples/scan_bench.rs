@@ -0,0 +1,79 @@
+//! Generates a fixture tree of fake projects, scans it with the serial and the
+//! parallel scanner and checks that both report the same folders.
+//!
+//! Run with `cargo run --release -p dev_cleaner_core --example scan_bench [repos] [threads]`.
+
+use dev_cleaner_core::{
+    config::Config,
+    folderscan::{FoundFolder, scan_folders, scan_folders_parallel},
+};
+use std::{fs, io, path::Path, time::Instant};
+
+fn create_fixture(root: &Path, repos: usize) -> io::Result<()> {
+    for i in 0..repos {
+        let repo = root
+            .join(format!("group-{}", i % 16))
+            .join(format!("repo-{i}"));
+        for dir in ["src/nested/deeper", "docs", "build"] {
+            fs::create_dir_all(repo.join(dir))?;
+            fs::write(repo.join(dir).join("file.txt"), "content")?;
+        }
+
+        match i % 3 {
+            0 => {
+                fs::write(repo.join("Cargo.toml"), "[package]")?;
+                fs::create_dir_all(repo.join("target/debug/deps"))?;
+                fs::write(repo.join("target/debug/deps/lib.rlib"), vec![0u8; 4096])?;
+            }
+            1 => {
+                fs::write(repo.join("package.json"), "{}")?;
+                for module in 0..8 {
+                    let module = repo.join(format!("node_modules/pkg-{module}/node_modules/dep"));
+                    fs::create_dir_all(&module)?;
+                    fs::write(module.join("index.js"), "module.exports = {}")?;
+                }
+            }
+            _ => {
+                fs::create_dir_all(repo.join("src/__pycache__"))?;
+                fs::write(repo.join("src/__pycache__/mod.pyc"), vec![0u8; 512])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn paths(found: &[FoundFolder]) -> Vec<&Path> {
+    let mut paths = found.iter().map(|f| f.path.as_path()).collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let repos = args.next().map(|a| a.parse()).transpose()?.unwrap_or(500);
+    let threads = args.next().map(|a| a.parse()).transpose()?.unwrap_or(0);
+
+    let root = std::env::temp_dir().join(format!("dev_cleaner_bench_{}", std::process::id()));
+    create_fixture(&root, repos)?;
+    let filters = Config::default().filters.compile()?;
+
+    let start = Instant::now();
+    let serial = scan_folders(&root, &filters);
+    let serial_time = start.elapsed();
+
+    let start = Instant::now();
+    let parallel = scan_folders_parallel(&root, &filters, threads);
+    let parallel_time = start.elapsed();
+
+    fs::remove_dir_all(&root)?;
+
+    println!("repos:    {repos}");
+    println!("found:    {}", serial.len());
+    println!("serial:   {serial_time:?}");
+    println!("parallel: {parallel_time:?}");
+
+    assert_eq!(paths(&serial), paths(&parallel), "scanners disagree");
+    assert_eq!(serial.len(), repos, "expected one match per repo");
+    println!("serial and parallel scanners agree");
+    Ok(())
+}
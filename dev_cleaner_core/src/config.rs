@@ -1,19 +1,80 @@
-use crate::filters::Filters;
+use crate::{
+    excludes::Excludes,
+    filters::{Filter, FilterMatcher, FilterRule, Filters, Marker},
+    mounts::{DEFAULT_SKIPPED_FS_TYPES, MountPolicy},
+    presets::Preset,
+    remover::RemovalMethod,
+    usage::{Age, ByteSize},
+};
+use owo_colors::OwoColorize;
 use serde::{Deserialize, Serialize};
-use std::fmt::Display;
+use std::{fmt::Display, path::PathBuf};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Config {
     pub filters: Filters,
+    /// Folders scanned when none are given.
+    #[serde(default)]
+    pub roots: Vec<PathBuf>,
+    /// Built-in filter bundles used in addition to `filters`.
+    #[serde(default)]
+    pub presets: Vec<Preset>,
+    /// Also match any folder containing a valid `CACHEDIR.TAG`, whatever its name.
+    #[serde(default)]
+    pub cachedir_tags: bool,
+    /// Number of scanner threads, `0` uses one per core.
+    #[serde(default)]
+    pub threads: usize,
+    /// How selected folders are removed.
+    #[serde(default)]
+    pub removal: RemovalMethod,
+    /// Paths that are never removed, nor anything inside them.
+    #[serde(default)]
+    pub protected: Vec<PathBuf>,
+    /// Only report folders whose project was not modified for this long.
+    /// Filters may set their own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_age: Option<Age>,
+    /// Hide folders smaller than this. Filters may set their own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_size: Option<ByteSize>,
+    /// Never leave the filesystem of the scanned folder.
+    #[serde(default)]
+    pub same_filesystem: bool,
+    /// Types of mounted filesystems never scanned into, globs allowed (e.g. `fuse.*`).
+    #[serde(default = "default_skipped_fs_types")]
+    pub skipped_fs_types: Vec<String>,
+}
+
+pub fn default_skipped_fs_types() -> Vec<String> {
+    DEFAULT_SKIPPED_FS_TYPES.map(String::from).to_vec()
 }
 
 impl Default for Config {
     fn default() -> Self {
+        let filter = |name: &str, marker: Marker| {
+            Filter::new(FilterRule::Name(name.to_string())).with_markers([marker])
+        };
+        let sibling = |name: &str| Marker::Sibling(name.to_string());
+
         Self {
-            filters: vec!["target", "node_modules", "dist", "out", "__pycache__"]
-                .into_iter()
-                .map(String::from)
-                .collect(),
+            filters: Filters::new(vec![
+                filter("target", sibling("Cargo.toml")),
+                filter("node_modules", sibling("package.json")),
+                filter("dist", sibling("package.json")),
+                filter("out", sibling("package.json")),
+                filter("__pycache__", Marker::Contains("*.pyc".to_string())),
+            ]),
+            roots: Vec::new(),
+            presets: Vec::new(),
+            cachedir_tags: false,
+            threads: 0,
+            removal: RemovalMethod::default(),
+            protected: Vec::new(),
+            min_age: None,
+            min_size: None,
+            same_filesystem: false,
+            skipped_fs_types: default_skipped_fs_types(),
         }
     }
 }
@@ -21,16 +82,134 @@ impl Default for Config {
 impl Display for Config {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         crate::display_field!(f, "Filters", self.filters);
+        let roots = match self.roots.is_empty() {
+            true => "\t(no default folders)".to_string(),
+            false => self
+                .roots
+                .iter()
+                .map(|p| format!("\t- {}", p.display()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        crate::display_field!(f, "Roots", roots);
+        let mut presets = self
+            .presets
+            .iter()
+            .flat_map(|preset| {
+                let filters = preset
+                    .filters()
+                    .into_iter()
+                    .map(|filter| format!("\t\t{filter}"));
+                std::iter::once(format!("\t- {preset}")).chain(filters)
+            })
+            .collect::<Vec<_>>();
+        let available = Preset::ALL
+            .into_iter()
+            .filter(|preset| !self.presets.contains(preset))
+            .map(Preset::name)
+            .collect::<Vec<_>>();
+        if !available.is_empty() {
+            presets.push(format!(
+                "\t{}",
+                format!(
+                    "Available: {} (config presets enable <name>)",
+                    available.join(", ")
+                )
+                .cyan()
+            ));
+        }
+        crate::display_field!(f, "Presets", presets.join("\n"));
+        let cachedir_tags = match self.cachedir_tags {
+            true => "enabled",
+            false => "disabled",
+        };
+        crate::display_field!(f, "CACHEDIR.TAG", format!("\t{cachedir_tags}"));
+        let threads = match self.threads {
+            0 => "auto".to_string(),
+            n => n.to_string(),
+        };
+        crate::display_field!(f, "Threads", format!("\t{threads}"));
+        crate::display_field!(f, "Removal", format!("\t{}", self.removal));
+        let protected = match self.protected.is_empty() {
+            true => "\t(no protected paths)".to_string(),
+            false => self
+                .protected
+                .iter()
+                .map(|p| format!("\t- {}", p.display()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        crate::display_field!(f, "Protected", protected);
+        let min_age = match self.min_age {
+            Some(age) => format!("\tolder than {age}"),
+            None => "\t(any age)".to_string(),
+        };
+        crate::display_field!(f, "Min age", min_age);
+        let min_size = match self.min_size {
+            Some(size) => format!("\tat least {size}"),
+            None => "\t(any size)".to_string(),
+        };
+        crate::display_field!(f, "Min size", min_size);
+        let filesystems = format!(
+            "\t{}\n\tskipping {}",
+            match self.same_filesystem {
+                true => "stay on the scanned filesystem",
+                false => "enter other filesystems",
+            },
+            match self.skipped_fs_types.is_empty() {
+                true => "no filesystem types".to_string(),
+                false => self.skipped_fs_types.join(", "),
+            }
+        );
+        crate::display_field!(f, "Filesystems", filesystems);
+        if let Ok(path) = Self::ignore_file_path() {
+            crate::display_field!(f, "Ignore file", format!("\t{}", path.display()));
+        }
         Ok(())
     }
 }
 
 impl Config {
     const CONFIG_PATH: &'static str = "config";
+    const IGNORE_FILE: &'static str = "ignore";
     pub fn new() -> Config {
         Config::default()
     }
 
+    /// The configured filters followed by those of all enabled presets.
+    pub fn active_filters(&self) -> Filters {
+        let mut filters = self.filters.clone();
+        for filter in self.presets.iter().flat_map(|preset| preset.filters()) {
+            if !filters.contains(&filter) {
+                filters.push(filter);
+            }
+        }
+        filters
+    }
+
+    /// Compiles the active filters together with the configured thresholds,
+    /// `CACHEDIR.TAG` matching and the global ignore file.
+    pub fn matcher(&self) -> Result<FilterMatcher, Box<dyn std::error::Error>> {
+        Ok(self
+            .active_filters()
+            .compile()?
+            .with_min_age(self.min_age)
+            .with_min_size(self.min_size)
+            .with_cachedir_tags(self.cachedir_tags)
+            .with_mounts(MountPolicy::new(
+                self.same_filesystem,
+                &self.skipped_fs_types,
+            )?)
+            .with_excludes(Excludes::global(&Self::ignore_file_path()?)?))
+    }
+
+    /// The global exclude file in gitignore syntax, next to the configuration file.
+    pub fn ignore_file_path() -> Result<PathBuf, std::io::Error> {
+        confy::get_configuration_file_path("dev_cleaner", Self::CONFIG_PATH)
+            .map(|path| path.with_file_name(Self::IGNORE_FILE))
+            .map_err(|e| std::io::Error::other(e.to_string()))
+    }
+
     pub fn load() -> Result<Config, std::io::Error> {
         confy::load("dev_cleaner", Self::CONFIG_PATH)
             .map_err(|e| std::io::Error::other(e.to_string()))
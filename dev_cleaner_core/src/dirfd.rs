@@ -0,0 +1,123 @@
+//! Removing folders relative to open directory descriptors.
+//!
+//! A folder is opened with `O_NOFOLLOW` relative to its opened parent and
+//! compared with the device and inode seen by the scan. Its contents are then
+//! removed through that descriptor, one `openat`/`unlinkat` at a time, so a
+//! folder swapped for a symlink can never redirect the removal elsewhere.
+
+use std::{
+    ffi::{CStr, OsStr},
+    io,
+    os::{fd::OwnedFd, unix::ffi::OsStrExt},
+    path::Path,
+};
+
+use rustix::{
+    fs::{AtFlags, Dir, FileType, Mode, OFlags},
+    io::Errno,
+};
+
+use crate::{folderscan::FolderId, remover::RemovalError};
+
+const DIR_FLAGS: OFlags = OFlags::RDONLY
+    .union(OFlags::DIRECTORY)
+    .union(OFlags::NOFOLLOW)
+    .union(OFlags::CLOEXEC);
+
+/// A folder opened without following symlinks, together with its parent.
+pub struct OpenedFolder<'a> {
+    pub parent: OwnedFd,
+    pub name: &'a OsStr,
+    pub dir: OwnedFd,
+}
+
+/// Opens the folder at `path`, failing if it is a symlink, not a directory or
+/// not the folder identified by `expected`.
+pub fn open_folder(path: &Path, expected: Option<FolderId>) -> io::Result<OpenedFolder<'_>> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "cannot remove a root"))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let parent = rustix::fs::open(
+        parent,
+        OFlags::RDONLY | OFlags::DIRECTORY | OFlags::CLOEXEC,
+        Mode::empty(),
+    )?;
+    let dir = rustix::fs::openat(&parent, name, DIR_FLAGS, Mode::empty()).map_err(|e| match e {
+        Errno::LOOP | Errno::NOTDIR => io::Error::new(
+            io::ErrorKind::NotADirectory,
+            "not a directory, or a symlink",
+        ),
+        e => e.into(),
+    })?;
+
+    if let Some(expected) = expected {
+        let stat = rustix::fs::fstat(&dir)?;
+        let id = FolderId {
+            dev: stat.st_dev as u64,
+            ino: stat.st_ino as u64,
+        };
+        if id != expected {
+            return Err(io::Error::other(
+                "replaced by another folder since the scan",
+            ));
+        }
+    }
+    Ok(OpenedFolder { parent, name, dir })
+}
+
+/// Deletes the folder at `path` and everything inside it, see the module docs.
+pub fn remove_folder(path: &Path, expected: Option<FolderId>) -> Result<(), RemovalError> {
+    let folder = open_folder(path, expected).map_err(|e| RemovalError::new(path, e))?;
+    remove_contents(&folder.dir, path)?;
+    rustix::fs::unlinkat(&folder.parent, folder.name, AtFlags::REMOVEDIR)
+        .map_err(|e| RemovalError::new(path, e.into()))
+}
+
+/// Empties the directory open as `dir`, whose path is only used for errors.
+fn remove_contents(dir: &OwnedFd, path: &Path) -> Result<(), RemovalError> {
+    let mut entries = Dir::read_from(dir).map_err(|e| RemovalError::new(path, e.into()))?;
+
+    while let Some(entry) = entries.read() {
+        let entry = entry.map_err(|e| RemovalError::new(path, e.into()))?;
+        let name = entry.file_name();
+        if name == c"." || name == c".." {
+            continue;
+        }
+
+        let entry_path = path.join(OsStr::from_bytes(name.to_bytes()));
+        let error = |e: Errno| RemovalError::new(&entry_path, e.into());
+        match is_dir(dir, name, entry.file_type()).map_err(error)? {
+            true => {
+                let child =
+                    rustix::fs::openat(dir, name, DIR_FLAGS, Mode::empty()).map_err(error)?;
+                remove_contents(&child, &entry_path)?;
+                rustix::fs::unlinkat(dir, name, AtFlags::REMOVEDIR).map_err(error)?;
+            }
+            false => rustix::fs::unlinkat(dir, name, AtFlags::empty()).map_err(error)?,
+        }
+    }
+    Ok(())
+}
+
+/// Whether the entry `name` of `dir` is a real directory, without following symlinks.
+fn is_dir(dir: &OwnedFd, name: &CStr, file_type: FileType) -> Result<bool, Errno> {
+    match file_type {
+        FileType::Unknown => {
+            let stat = rustix::fs::statat(dir, name, AtFlags::SYMLINK_NOFOLLOW)?;
+            Ok(FileType::from_raw_mode(stat.st_mode) == FileType::Directory)
+        }
+        file_type => Ok(file_type == FileType::Directory),
+    }
+}
+
+/// Renames the folder at `path` to `target`, relative to its opened parent.
+pub fn rename_folder(path: &Path, expected: Option<FolderId>, target: &Path) -> io::Result<()> {
+    let folder = open_folder(path, expected)?;
+    rustix::fs::renameat(&folder.parent, folder.name, rustix::fs::CWD, target)?;
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Per-directory exclude file, using gitignore syntax relative to its directory.
+pub const IGNORE_FILE_NAME: &str = ".devcleanerignore";
+/// Marker file that keeps the directory containing it, and everything inside it,
+/// out of every scan.
+pub const KEEP_FILE_NAME: &str = ".devcleaner-keep";
+
+/// Gitignore-style exclude files in effect for a directory.
+///
+/// Each level holds the `.devcleanerignore` of one directory and points to the
+/// levels of its ancestors, ending with the global exclude file. Like git, the
+/// innermost file with a matching pattern decides, so `!pattern` lines can
+/// re-include folders excluded further up.
+#[derive(Debug, Clone)]
+pub struct Excludes {
+    matcher: Gitignore,
+    parent: Option<Arc<Excludes>>,
+}
+
+impl Default for Excludes {
+    fn default() -> Self {
+        Excludes {
+            matcher: Gitignore::empty(),
+            parent: None,
+        }
+    }
+}
+
+impl Excludes {
+    /// Loads the global exclude file at `path`, if it exists.
+    ///
+    /// Its patterns are anchored at the filesystem root, so `/home/me/dist`
+    /// excludes a single folder while `dist` excludes every folder of that name.
+    pub fn global(path: &Path) -> io::Result<Excludes> {
+        if !path.exists() {
+            return Ok(Excludes::default());
+        }
+
+        let mut builder = GitignoreBuilder::new("/");
+        if let Some(error) = builder.add(path) {
+            return Err(io::Error::other(error.to_string()));
+        }
+        let matcher = builder
+            .build()
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        Ok(Excludes {
+            matcher,
+            parent: None,
+        })
+    }
+
+    /// Adds the `.devcleanerignore` of `dir` on top of `self`. Lines that fail to
+    /// parse are skipped.
+    pub fn nested(self: &Arc<Self>, dir: &Path) -> Arc<Excludes> {
+        let (matcher, _) = Gitignore::new(dir.join(IGNORE_FILE_NAME));
+        match matcher.is_empty() {
+            true => Arc::clone(self),
+            false => Arc::new(Excludes {
+                matcher,
+                parent: Some(Arc::clone(self)),
+            }),
+        }
+    }
+
+    /// The excludes in effect for `path`, adding the `.devcleanerignore` of each
+    /// of its ancestors on top of `self`. Returns `None` if `path` or one of its
+    /// ancestors is excluded, as a scan would never reach it.
+    pub fn along(self: &Arc<Self>, path: &Path) -> Option<Arc<Excludes>> {
+        let ancestors = path.ancestors().collect::<Vec<_>>();
+        let mut excludes = Arc::clone(self);
+        for pair in ancestors.windows(2).rev() {
+            let (path, dir) = (pair[0], pair[1]);
+            excludes = excludes.nested(dir);
+            if excludes.is_excluded(path, true) {
+                return None;
+            }
+        }
+        Some(excludes)
+    }
+
+    /// Returns `true` if the innermost matching pattern excludes `path`.
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        let mut excludes = Some(self);
+        while let Some(current) = excludes {
+            let matched = current.matcher.matched(path, is_dir);
+            if !matched.is_none() {
+                return matched.is_ignore();
+            }
+            excludes = current.parent.as_deref();
+        }
+        false
+    }
+}
+
+/// Returns the nearest ancestor of `path`, or `path` itself, containing a
+/// `.devcleaner-keep` marker.
+pub fn kept_by(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join(KEEP_FILE_NAME).exists())
+        .map(Path::to_path_buf)
+}
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize, Serializer};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    filters::FilterMatcher,
+    folderscan::{FolderId, FoundFolder},
+    safety::Violation,
+};
+
+/// Version of the [`ScanReport`] schema, bumped on incompatible changes only.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Results of a scan in the stable format printed by `scan --format json`.
+///
+/// Written by `scan --out` it doubles as a deletion plan: entries can be
+/// removed by hand before `clean --plan` removes the remaining folders.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScanReport {
+    pub version: u32,
+    /// The scanned folders, after removing nested ones.
+    #[serde(serialize_with = "lossy_paths")]
+    pub roots: Vec<PathBuf>,
+    pub folders: Vec<ScanRecord>,
+}
+
+/// One found folder, flat so that it also fits a CSV row or an NDJSON line.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScanRecord {
+    /// Paths that are not valid UTF-8 are written with replacement characters
+    /// instead of failing the whole report. Such entries no longer point at
+    /// their folder and are refused as missing by `clean --plan`.
+    #[serde(serialize_with = "lossy_path")]
+    pub path: PathBuf,
+    /// The filter rule that matched, or `CACHEDIR.TAG`.
+    pub rule: String,
+    /// Bytes allocated on disk, i.e. what removing the folder frees.
+    pub size: u64,
+    /// Sum of the file lengths.
+    pub apparent_size: u64,
+    pub file_count: u64,
+    /// Most recent modification inside the folder, in RFC 3339.
+    pub newest_mtime: Option<String>,
+    /// The folder containing the matched one.
+    #[serde(serialize_with = "lossy_optional_path")]
+    pub project_root: Option<PathBuf>,
+    /// Device and inode at scan time, a folder with another identity is refused.
+    #[serde(default)]
+    pub device: Option<u64>,
+    #[serde(default)]
+    pub inode: Option<u64>,
+}
+
+impl ScanReport {
+    pub fn new(roots: Vec<PathBuf>, folders: &[FoundFolder]) -> ScanReport {
+        ScanReport {
+            version: SCHEMA_VERSION,
+            roots,
+            folders: folders.iter().map(ScanRecord::from).collect(),
+        }
+    }
+
+    /// Reads a plan written by `scan --out`.
+    pub fn load(path: &Path) -> io::Result<ScanReport> {
+        let report: ScanReport = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if report.version != SCHEMA_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported plan version {}", report.version),
+            ));
+        }
+        Ok(report)
+    }
+}
+
+impl ScanRecord {
+    /// Identity of the folder at scan time, if recorded.
+    pub fn id(&self) -> Option<FolderId> {
+        Some(FolderId {
+            dev: self.device?,
+            ino: self.inode?,
+        })
+    }
+
+    /// Checks that the folder is still the one that was scanned, still matched
+    /// by the same rule and not excluded since, then measures it again for
+    /// removal.
+    pub fn verify(&self, filters: &FilterMatcher) -> Result<FoundFolder, Violation> {
+        let metadata = self
+            .path
+            .symlink_metadata()
+            .map_err(|_| Violation::Missing)?;
+        if metadata.is_symlink() {
+            return Err(Violation::Symlink);
+        }
+        if !metadata.is_dir() {
+            return Err(Violation::NotADirectory);
+        }
+        if self.id().is_some() && self.id() != FolderId::of(&metadata) {
+            return Err(Violation::Replaced);
+        }
+        let reason = filters
+            .reason(&self.path)
+            .ok_or(Violation::NoLongerMatches)?;
+        if reason.to_string() != self.rule {
+            return Err(Violation::RuleChanged(reason.to_string()));
+        }
+        if filters.excludes().along(&self.path).is_none() {
+            return Err(Violation::Excluded);
+        }
+
+        let measures_project = filters.measures_project(&reason);
+        let mut folder = FoundFolder::measure(self.path.clone(), reason);
+        if measures_project
+            && let Some(excludes) = folder
+                .project_root()
+                .and_then(|root| filters.excludes().along(root))
+        {
+            folder.measure_project(&excludes, filters);
+        }
+        Ok(FoundFolder {
+            id: self.id().or(folder.id),
+            ..folder
+        })
+    }
+}
+
+impl From<&FoundFolder> for ScanRecord {
+    fn from(folder: &FoundFolder) -> Self {
+        ScanRecord {
+            path: folder.path.clone(),
+            rule: folder.reason.to_string(),
+            size: folder.usage.allocated_bytes,
+            apparent_size: folder.usage.apparent_bytes,
+            file_count: folder.usage.file_count,
+            newest_mtime: folder
+                .usage
+                .newest_mtime
+                .and_then(|time| jiff::Timestamp::try_from(time).ok())
+                .map(|time| time.to_string()),
+            project_root: folder.project_root().map(PathBuf::from),
+            device: folder.id.map(|id| id.dev),
+            inode: folder.id.map(|id| id.ino),
+        }
+    }
+}
+
+fn lossy_path<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&path.to_string_lossy())
+}
+
+fn lossy_optional_path<S: Serializer>(
+    path: &Option<PathBuf>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match path {
+        Some(path) => serializer.serialize_some(&path.to_string_lossy()),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn lossy_paths<S: Serializer>(paths: &[PathBuf], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(paths.iter().map(|path| path.to_string_lossy()))
+}
@@ -1,20 +1,600 @@
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::{
     fmt::Display,
+    fs,
+    io::Read,
     ops::{Deref, DerefMut},
+    path::Path,
+    str::FromStr,
+    sync::Arc,
 };
 
+use crate::{
+    excludes::Excludes,
+    mounts::MountPolicy,
+    usage::{Age, ByteSize},
+};
+
+fn compile_glob(pattern: &str) -> Result<GlobMatcher, String> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map(|g| g.compile_matcher())
+        .map_err(|e| e.to_string())
+}
+
+/// A single rule describing which folders are considered cleanable.
+///
+/// Rules are stored in their string form (see [`FilterRule::from_str`]), which keeps
+/// configuration files written with plain folder names loadable.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum FilterRule {
+    /// Folder name equals the value exactly, e.g. `node_modules`.
+    Name(String),
+    /// Folder name matches a glob, e.g. `cmake-build-*`.
+    Glob(String),
+    /// Folder name matches a regular expression.
+    Regex(String),
+    /// Trailing path components match, e.g. `android/app/build`.
+    Path(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterError {
+    Empty,
+    InvalidGlob(String),
+    InvalidRegex(String),
+    InvalidMarker(String),
+}
+
+impl Display for FilterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterError::Empty => write!(f, "filter pattern must not be empty"),
+            FilterError::InvalidGlob(e) => write!(f, "invalid glob pattern: {e}"),
+            FilterError::InvalidRegex(e) => write!(f, "invalid regex pattern: {e}"),
+            FilterError::InvalidMarker(e) => write!(f, "invalid marker: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+impl FilterRule {
+    const GLOB_CHARS: &'static [char] = &['*', '?', '[', '{'];
+
+    /// Returns the raw pattern without its kind prefix.
+    pub fn pattern(&self) -> &str {
+        match self {
+            FilterRule::Name(p)
+            | FilterRule::Glob(p)
+            | FilterRule::Regex(p)
+            | FilterRule::Path(p) => p,
+        }
+    }
+
+    /// Compiles the rule into a matcher, validating glob and regex syntax.
+    pub fn compile(&self) -> Result<CompiledRule, FilterError> {
+        let glob = |pattern: &str| compile_glob(pattern).map_err(FilterError::InvalidGlob);
+
+        match self {
+            FilterRule::Name(name) => Ok(CompiledRule::Name(name.clone())),
+            FilterRule::Glob(pattern) => glob(pattern).map(CompiledRule::Glob),
+            FilterRule::Regex(pattern) => Regex::new(pattern)
+                .map(CompiledRule::Regex)
+                .map_err(|e| FilterError::InvalidRegex(e.to_string())),
+            FilterRule::Path(pattern) => {
+                let pattern = pattern.trim_end_matches('/');
+                match pattern.starts_with('/') {
+                    true => glob(pattern),
+                    false => glob(&format!("**/{pattern}")),
+                }
+                .map(CompiledRule::Path)
+            }
+        }
+    }
+}
+
+/// Parses a rule from `<kind>:<pattern>`, where kind is one of `name`, `glob`,
+/// `regex` (or `re`) and `path`.
+///
+/// Without a prefix the kind is inferred: patterns containing `/` are paths,
+/// patterns containing glob metacharacters are globs and anything else is a name.
+impl FromStr for FilterRule {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let rule = match s.split_once(':') {
+            Some(("name", p)) => FilterRule::Name(p.to_string()),
+            Some(("glob", p)) => FilterRule::Glob(p.to_string()),
+            Some(("regex" | "re", p)) => FilterRule::Regex(p.to_string()),
+            Some(("path", p)) => FilterRule::Path(p.to_string()),
+            _ if s.contains('/') => FilterRule::Path(s.to_string()),
+            _ if s.contains(Self::GLOB_CHARS) => FilterRule::Glob(s.to_string()),
+            _ => FilterRule::Name(s.to_string()),
+        };
+
+        if rule.pattern().is_empty() {
+            return Err(FilterError::Empty);
+        }
+        rule.compile()?;
+        Ok(rule)
+    }
+}
+
+impl TryFrom<String> for FilterRule {
+    type Error = FilterError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<FilterRule> for String {
+    fn from(value: FilterRule) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for FilterRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // Only names that would parse as another rule need their prefix.
+            FilterRule::Name(p) if p.contains(['/', ':']) || p.contains(Self::GLOB_CHARS) => {
+                write!(f, "name:{p}")
+            }
+            FilterRule::Name(p) => write!(f, "{p}"),
+            FilterRule::Glob(p) => write!(f, "glob:{p}"),
+            FilterRule::Regex(p) => write!(f, "regex:{p}"),
+            FilterRule::Path(p) => write!(f, "path:{p}"),
+        }
+    }
+}
+
+/// A [`FilterRule`] ready to be evaluated against folder paths.
+#[derive(Debug, Clone)]
+pub enum CompiledRule {
+    Name(String),
+    Glob(GlobMatcher),
+    Regex(Regex),
+    Path(GlobMatcher),
+}
+
+impl CompiledRule {
+    /// Returns `true` if the folder at `path` is matched by this rule.
+    pub fn is_match(&self, path: &Path) -> bool {
+        let name = || path.file_name().and_then(|n| n.to_str());
+        match self {
+            CompiledRule::Name(n) => name().is_some_and(|name| name == n),
+            CompiledRule::Glob(g) => name().is_some_and(|name| g.is_match(name)),
+            CompiledRule::Regex(r) => name().is_some_and(|name| r.is_match(name)),
+            CompiledRule::Path(g) => g.is_match(path),
+        }
+    }
+}
+
+/// A file that has to be present for a [`Filter`] to report a folder.
+///
+/// Marker names may be globs, e.g. `*.csproj`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum Marker {
+    /// Entry next to the matched folder, e.g. `Cargo.toml` next to `target`.
+    Sibling(String),
+    /// Entry inside the matched folder, e.g. `*.pyc` inside `__pycache__`.
+    Contains(String),
+}
+
+impl Marker {
+    /// Returns the raw file name or glob without its kind prefix.
+    pub fn pattern(&self) -> &str {
+        match self {
+            Marker::Sibling(p) | Marker::Contains(p) => p,
+        }
+    }
+
+    fn compile(&self) -> Result<CompiledMarker, FilterError> {
+        let pattern = self.pattern();
+        let name = match pattern.contains(FilterRule::GLOB_CHARS) {
+            true => MarkerName::Glob(compile_glob(pattern).map_err(FilterError::InvalidMarker)?),
+            false => MarkerName::Literal(pattern.to_string()),
+        };
+        Ok(CompiledMarker {
+            sibling: matches!(self, Marker::Sibling(_)),
+            name,
+        })
+    }
+}
+
+/// Parses a marker from `sibling:<name>` or `contains:<name>`, defaulting to a sibling.
+impl FromStr for Marker {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let marker = match s.split_once(':') {
+            Some(("sibling", p)) => Marker::Sibling(p.to_string()),
+            Some(("contains", p)) => Marker::Contains(p.to_string()),
+            _ => Marker::Sibling(s.to_string()),
+        };
+
+        if marker.pattern().is_empty() || marker.pattern().contains('/') {
+            return Err(FilterError::InvalidMarker(format!(
+                "`{s}` must be a single file name"
+            )));
+        }
+        marker.compile()?;
+        Ok(marker)
+    }
+}
+
+impl TryFrom<String> for Marker {
+    type Error = FilterError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Marker> for String {
+    fn from(value: Marker) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for Marker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Marker::Sibling(p) => write!(f, "{p}"),
+            Marker::Contains(p) => write!(f, "contains:{p}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum MarkerName {
+    Literal(String),
+    Glob(GlobMatcher),
+}
+
+#[derive(Debug, Clone)]
+struct CompiledMarker {
+    sibling: bool,
+    name: MarkerName,
+}
+
+impl CompiledMarker {
+    fn is_present(&self, folder: &Path) -> bool {
+        let dir = match self.sibling {
+            true => match folder.parent() {
+                Some(parent) => parent,
+                None => return false,
+            },
+            false => folder,
+        };
+
+        match &self.name {
+            MarkerName::Literal(name) => dir.join(name).exists(),
+            MarkerName::Glob(glob) => fs::read_dir(dir).is_ok_and(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .any(|entry| glob.is_match(entry.file_name()))
+            }),
+        }
+    }
+}
+
+/// A [`FilterRule`] together with the markers a matched folder must satisfy.
+///
+/// A filter without markers matches on its rule alone, otherwise at least one
+/// of the markers has to be present.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "FilterRepr")]
+pub struct Filter {
+    pub rule: FilterRule,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub markers: Vec<Marker>,
+    /// Overrides the global minimum age for folders matched by this filter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_age: Option<Age>,
+    /// Overrides the global minimum size for folders matched by this filter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_size: Option<ByteSize>,
+}
+
+/// Accepts both the bare rule string and the full table form.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FilterRepr {
+    Rule(FilterRule),
+    Full {
+        rule: FilterRule,
+        #[serde(default)]
+        markers: Vec<Marker>,
+        #[serde(default)]
+        min_age: Option<Age>,
+        #[serde(default)]
+        min_size: Option<ByteSize>,
+    },
+}
+
+impl From<FilterRepr> for Filter {
+    fn from(value: FilterRepr) -> Self {
+        match value {
+            FilterRepr::Rule(rule) => Filter::new(rule),
+            FilterRepr::Full {
+                rule,
+                markers,
+                min_age,
+                min_size,
+            } => Filter {
+                rule,
+                markers,
+                min_age,
+                min_size,
+            },
+        }
+    }
+}
+
+impl Filter {
+    pub fn new(rule: FilterRule) -> Filter {
+        Filter {
+            rule,
+            markers: Vec::new(),
+            min_age: None,
+            min_size: None,
+        }
+    }
+
+    pub fn with_markers(self, markers: impl IntoIterator<Item = Marker>) -> Self {
+        Filter {
+            markers: markers.into_iter().collect(),
+            ..self
+        }
+    }
+
+    pub fn with_min_age(self, min_age: Option<Age>) -> Self {
+        Filter { min_age, ..self }
+    }
+
+    pub fn with_min_size(self, min_size: Option<ByteSize>) -> Self {
+        Filter { min_size, ..self }
+    }
+
+    /// Compiles the rule and markers of this filter.
+    pub fn compile(&self) -> Result<CompiledFilter, FilterError> {
+        Ok(CompiledFilter {
+            filter: self.clone(),
+            rule: self.rule.compile()?,
+            markers: self
+                .markers
+                .iter()
+                .map(Marker::compile)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl From<FilterRule> for Filter {
+    fn from(value: FilterRule) -> Self {
+        Filter::new(value)
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.rule)?;
+        if !self.markers.is_empty() {
+            let markers = self
+                .markers
+                .iter()
+                .map(Marker::to_string)
+                .collect::<Vec<_>>();
+            write!(f, " (requires {})", markers.join(" | "))?;
+        }
+        if let Some(min_age) = self.min_age {
+            write!(f, " (older than {min_age})")?;
+        }
+        if let Some(min_size) = self.min_size {
+            write!(f, " (at least {min_size})")?;
+        }
+        Ok(())
+    }
+}
+
+/// A [`Filter`] ready to be evaluated against folder paths.
+#[derive(Debug, Clone)]
+pub struct CompiledFilter {
+    filter: Filter,
+    rule: CompiledRule,
+    markers: Vec<CompiledMarker>,
+}
+
+impl CompiledFilter {
+    pub fn filter(&self) -> &Filter {
+        &self.filter
+    }
+
+    /// Returns `true` if the rule matches the folder at `path`.
+    pub fn rule_matches(&self, path: &Path) -> bool {
+        self.rule.is_match(path)
+    }
+
+    /// Returns `true` if the filter has no markers or any of them is present.
+    pub fn markers_present(&self, path: &Path) -> bool {
+        self.markers.is_empty() || self.markers.iter().any(|m| m.is_present(path))
+    }
+}
+
+/// Why the scanner matched a folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatchReason {
+    /// Matched by a configured or preset filter.
+    Filter(Filter),
+    /// Contains a valid `CACHEDIR.TAG`, see <https://bford.info/cachedir/>.
+    CacheDirTag,
+}
+
+impl Display for MatchReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatchReason::Filter(filter) => write!(f, "{}", filter.rule),
+            MatchReason::CacheDirTag => write!(f, "CACHEDIR.TAG"),
+        }
+    }
+}
+
+/// Returns `true` if `dir` contains a `CACHEDIR.TAG` starting with the standard signature.
+fn has_cachedir_tag(dir: &Path) -> bool {
+    const SIGNATURE: &[u8; 43] = b"Signature: 8a477f597d28d172789f06886806bc55";
+
+    let mut header = [0; SIGNATURE.len()];
+    fs::File::open(dir.join("CACHEDIR.TAG"))
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|_| &header == SIGNATURE)
+}
+
+/// The compiled form of [`Filters`], used by the scanner.
+#[derive(Debug, Clone, Default)]
+pub struct FilterMatcher {
+    filters: Vec<CompiledFilter>,
+    min_age: Option<Age>,
+    min_size: Option<ByteSize>,
+    cachedir_tags: bool,
+    project_age: bool,
+    excludes: Arc<Excludes>,
+    mounts: MountPolicy,
+}
+
+impl FilterMatcher {
+    /// Sets the minimum age of filters that do not set their own.
+    pub fn with_min_age(self, min_age: Option<Age>) -> Self {
+        FilterMatcher { min_age, ..self }
+    }
+
+    /// Sets the minimum size of filters that do not set their own.
+    pub fn with_min_size(self, min_size: Option<ByteSize>) -> Self {
+        FilterMatcher { min_size, ..self }
+    }
+
+    /// Also matches any folder containing a valid `CACHEDIR.TAG`.
+    pub fn with_cachedir_tags(self, cachedir_tags: bool) -> Self {
+        FilterMatcher {
+            cachedir_tags,
+            ..self
+        }
+    }
+
+    /// Measures the project of every match, so that its age covers the
+    /// whole project. Matches with a minimum age always have it measured.
+    pub fn with_project_age(self, project_age: bool) -> Self {
+        FilterMatcher {
+            project_age,
+            ..self
+        }
+    }
+
+    /// Returns `true` if the project of folders matched for `reason` is measured.
+    pub fn measures_project(&self, reason: &MatchReason) -> bool {
+        self.project_age || self.min_age(reason).is_some()
+    }
+
+    /// Sets the global exclude file, applied before any `.devcleanerignore`.
+    pub fn with_excludes(self, excludes: Excludes) -> Self {
+        FilterMatcher {
+            excludes: Arc::new(excludes),
+            ..self
+        }
+    }
+
+    /// The global excludes the scan starts with.
+    pub fn excludes(&self) -> Arc<Excludes> {
+        Arc::clone(&self.excludes)
+    }
+
+    /// Sets which mounted filesystems the scan may enter.
+    pub fn with_mounts(self, mounts: MountPolicy) -> Self {
+        FilterMatcher { mounts, ..self }
+    }
+
+    pub fn mounts(&self) -> &MountPolicy {
+        &self.mounts
+    }
+
+    /// The minimum age folders matched for `reason` must have to be reported.
+    pub fn min_age(&self, reason: &MatchReason) -> Option<Age> {
+        match reason {
+            MatchReason::Filter(filter) => filter.min_age.or(self.min_age),
+            MatchReason::CacheDirTag => self.min_age,
+        }
+    }
+
+    /// The minimum allocated size folders matched for `reason` must have to be reported.
+    pub fn min_size(&self, reason: &MatchReason) -> Option<ByteSize> {
+        match reason {
+            MatchReason::Filter(filter) => filter.min_size.or(self.min_size),
+            MatchReason::CacheDirTag => self.min_size,
+        }
+    }
+
+    /// Returns the first filter whose rule matches the folder at `path` and whose
+    /// markers are satisfied, if any.
+    pub fn find(&self, path: &Path) -> Option<&Filter> {
+        self.filters
+            .iter()
+            .find(|f| f.rule_matches(path) && f.markers_present(path))
+            .map(CompiledFilter::filter)
+    }
+
+    /// Returns why the folder at `path` matches, filters taking precedence over
+    /// a `CACHEDIR.TAG`.
+    pub fn reason(&self, path: &Path) -> Option<MatchReason> {
+        match self.find(path) {
+            Some(filter) => Some(MatchReason::Filter(filter.clone())),
+            None => {
+                (self.cachedir_tags && has_cachedir_tag(path)).then_some(MatchReason::CacheDirTag)
+            }
+        }
+    }
+
+    /// Returns `true` if any filter, or a `CACHEDIR.TAG`, matches the folder at `path`.
+    pub fn is_match(&self, path: &Path) -> bool {
+        self.find(path).is_some() || (self.cachedir_tags && has_cachedir_tag(path))
+    }
+}
+
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
-pub struct Filters(Vec<String>);
+pub struct Filters(Vec<Filter>);
 
 impl Filters {
-    pub fn new(values: Vec<String>) -> Filters {
+    pub fn new(values: Vec<Filter>) -> Filters {
         Self(values)
     }
+
+    /// Compiles all filters into a [`FilterMatcher`].
+    pub fn compile(&self) -> Result<FilterMatcher, FilterError> {
+        let filters = self
+            .0
+            .iter()
+            .map(Filter::compile)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(FilterMatcher {
+            filters,
+            min_age: None,
+            min_size: None,
+            cachedir_tags: false,
+            project_age: false,
+            excludes: Arc::default(),
+            mounts: MountPolicy::default(),
+        })
+    }
 }
 
 impl Deref for Filters {
-    type Target = Vec<String>;
+    type Target = Vec<Filter>;
     fn deref(&self) -> &Self::Target {
         &self.0
     }
@@ -26,23 +606,23 @@ impl DerefMut for Filters {
     }
 }
 
-impl std::iter::FromIterator<std::string::String> for Filters {
-    fn from_iter<T: IntoIterator<Item = std::string::String>>(iter: T) -> Self {
+impl std::iter::FromIterator<Filter> for Filters {
+    fn from_iter<T: IntoIterator<Item = Filter>>(iter: T) -> Self {
         Self(Vec::from_iter(iter))
     }
 }
 
 impl std::iter::IntoIterator for Filters {
-    type Item = String;
-    type IntoIter = std::vec::IntoIter<String>;
+    type Item = Filter;
+    type IntoIter = std::vec::IntoIter<Filter>;
     fn into_iter(self) -> Self::IntoIter {
         self.0.into_iter()
     }
 }
 
 impl<'a> std::iter::IntoIterator for &'a Filters {
-    type Item = &'a String;
-    type IntoIter = std::slice::Iter<'a, String>;
+    type Item = &'a Filter;
+    type IntoIter = std::slice::Iter<'a, Filter>;
     fn into_iter(self) -> Self::IntoIter {
         self.0.iter()
     }
@@ -62,8 +642,37 @@ impl Display for Filters {
         writeln!(
             f,
             "\t- {}",
-            "To edit use: {add | remove} <value>".to_string().cyan()
+            "To edit use: {add | remove} [name: | glob: | regex: | path:]<value> [--marker <file>] [--older-than <age>] [--min-size <size>]"
+                .to_string()
+                .cyan()
         )?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_survive_display_and_parse() {
+        let rules = [
+            FilterRule::Name("target".to_string()),
+            FilterRule::Name("cmake-build-*".to_string()),
+            FilterRule::Name("a:b".to_string()),
+            FilterRule::Name("glob:x".to_string()),
+            FilterRule::Glob("cmake-build-*".to_string()),
+            FilterRule::Glob("plain".to_string()),
+            FilterRule::Regex("^build-\\d+$".to_string()),
+            FilterRule::Path("**/.venv".to_string()),
+            FilterRule::Path("plain".to_string()),
+        ];
+        for rule in rules {
+            assert_eq!(
+                rule.to_string().parse::<FilterRule>(),
+                Ok(rule.clone()),
+                "{rule}"
+            );
+        }
+    }
+}
@@ -3,58 +3,770 @@ use hashbrown::HashSet;
 use std::{
     fs,
     path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, Receiver},
+    },
+    time::{Instant, SystemTime},
 };
-use walkdir::WalkDir;
 
-pub fn scan_folders(path: impl AsRef<Path>, target_dirs: &[&str]) -> Vec<PathBuf> {
-    let name_matches_target = |p: &Path| {
-        p.file_name()
-            .and_then(|n| n.to_str())
-            .is_some_and(|n| target_dirs.contains(&n))
+use crate::{
+    excludes::{self, Excludes, IGNORE_FILE_NAME, KEEP_FILE_NAME},
+    filters::{FilterMatcher, MatchReason},
+    mounts::SkippedMount,
+    remover::{Refusal, Removal, RemovalOutcome, RemovalReport, Remover},
+    safety::SafetyGuard,
+    usage::{Age, ByteSize, DiskUsage},
+};
+
+/// Device and inode of a folder, used to notice it was replaced after the scan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FolderId {
+    pub dev: u64,
+    pub ino: u64,
+}
+
+impl FolderId {
+    /// Identity of the file described by `metadata`, `None` where unsupported.
+    #[cfg(unix)]
+    pub fn of(metadata: &fs::Metadata) -> Option<FolderId> {
+        use std::os::unix::fs::MetadataExt;
+        Some(FolderId {
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn of(_metadata: &fs::Metadata) -> Option<FolderId> {
+        None
+    }
+}
+
+/// A folder matched by the scanner, together with its disk usage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoundFolder {
+    pub path: PathBuf,
+    pub usage: DiskUsage,
+    pub reason: MatchReason,
+    /// Identity at scan time, checked again right before removal.
+    pub id: Option<FolderId>,
+    /// Most recent modification in the project root, outside of matched
+    /// folders, see [`FoundFolder::measure_project`].
+    pub project_mtime: Option<SystemTime>,
+}
+
+impl FoundFolder {
+    /// Measures the folder at `path`. Unreadable folders are reported as empty.
+    pub fn measure(path: PathBuf, reason: MatchReason) -> FoundFolder {
+        let id = path
+            .symlink_metadata()
+            .ok()
+            .and_then(|metadata| FolderId::of(&metadata));
+        let usage = DiskUsage::of(&path).unwrap_or_default();
+        FoundFolder {
+            path,
+            usage,
+            reason,
+            id,
+            project_mtime: None,
+        }
+    }
+
+    /// Finds the most recent modification in the project root, so that the age
+    /// covers the project. `excludes` are those in effect for the project root.
+    ///
+    /// The project is walked like a scan would, skipping matched and excluded
+    /// folders, kept directories and the mounts skipped by `filters`.
+    pub fn measure_project(&mut self, excludes: &Arc<Excludes>, filters: &FilterMatcher) {
+        self.project_mtime = self
+            .project_root()
+            .and_then(|root| newest_mtime(root, excludes, filters));
+    }
+
+    /// The project root, i.e. the folder containing the match.
+    pub fn project_root(&self) -> Option<&Path> {
+        self.path.parent()
+    }
+
+    /// Most recent modification inside the folder or its project.
+    pub fn last_modified(&self) -> Option<SystemTime> {
+        self.usage.newest_mtime.max(self.project_mtime)
+    }
+
+    /// Time since the folder or its project was last modified.
+    pub fn age(&self) -> Option<Age> {
+        self.last_modified().map(Age::since)
+    }
+}
+
+/// Why a matched folder was left out of the results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Skipped {
+    TooRecent,
+    TooSmall,
+}
+
+/// Measures a matched folder and applies the thresholds of its filter. The
+/// project is only measured when `filters` need its age, with the `excludes`
+/// in effect for it.
+fn measure_match(
+    (path, reason): (PathBuf, MatchReason),
+    excludes: &Arc<Excludes>,
+    filters: &FilterMatcher,
+) -> Result<FoundFolder, Skipped> {
+    let min_age = filters.min_age(&reason);
+    let min_size = filters.min_size(&reason);
+    let measures_project = filters.measures_project(&reason);
+    let mut folder = FoundFolder::measure(path, reason);
+
+    if min_size.is_some_and(|min_size| ByteSize(folder.usage.allocated_bytes) < min_size) {
+        return Err(Skipped::TooSmall);
+    }
+    if measures_project {
+        folder.measure_project(excludes, filters);
+    }
+    if let Some(min_age) = min_age
+        && folder.age().is_some_and(|age| age < min_age)
+    {
+        return Err(Skipped::TooRecent);
+    }
+    Ok(folder)
+}
+
+/// Subdirectories of a single directory, split by whether a filter matched them.
+#[derive(Debug, Default)]
+struct DirScan {
+    matched: Vec<(PathBuf, MatchReason)>,
+    descend: Vec<PathBuf>,
+    /// Exclude files in effect for the folders in `descend`.
+    excludes: Arc<Excludes>,
+    skipped_mounts: Vec<SkippedMount>,
+}
+
+/// Reads the subdirectories of `dir`. Matched folders are never descended
+/// into, which prunes their whole subtree from the walk.
+///
+/// Folders excluded by a `.devcleanerignore` are neither matched nor descended
+/// into, and a `.devcleaner-keep` marker skips its directory altogether.
+fn scan_dir(dir: &Path, excludes: &Arc<Excludes>, filters: &FilterMatcher) -> DirScan {
+    let mut scan = DirScan {
+        excludes: Arc::clone(excludes),
+        ..DirScan::default()
+    };
+    let Some((entries, excludes)) = read_dir(dir, excludes) else {
+        return scan;
+    };
+    scan.excludes = excludes;
+    let mounts = filters.mounts();
+    let dir_metadata = match mounts.is_active() {
+        true => dir.symlink_metadata().ok(),
+        false => None,
     };
 
-    let skip_dirs: HashSet<PathBuf, RandomState> = HashSet::with_hasher(RandomState::new());
+    for entry in entries {
+        if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
 
-    WalkDir::new(path)
-        .into_iter()
+        let entry_path = entry.path();
+        if scan.excludes.is_excluded(&entry_path, true) {
+            continue;
+        }
+        if let Some(dir_metadata) = &dir_metadata
+            && let Ok(metadata) = entry.metadata()
+            && let Some(fs_type) = mounts.skips(dir_metadata, &metadata)
+        {
+            scan.skipped_mounts.push(SkippedMount {
+                path: entry_path,
+                fs_type,
+            });
+            continue;
+        }
+        match filters.reason(&entry_path) {
+            Some(_) if entry_path.join(KEEP_FILE_NAME).exists() => {}
+            Some(reason) => scan.matched.push((entry_path, reason)),
+            None => scan.descend.push(entry_path),
+        }
+    }
+    scan
+}
+
+/// Reads the entries of `dir` together with the excludes in effect for them.
+/// Returns `None` for unreadable directories and those with a `.devcleaner-keep`
+/// marker.
+fn read_dir(dir: &Path, excludes: &Arc<Excludes>) -> Option<(Vec<fs::DirEntry>, Arc<Excludes>)> {
+    let entries = fs::read_dir(dir)
+        .ok()?
         .filter_map(Result::ok)
-        .scan(skip_dirs, |skip_dirs, entry| {
-            let entry_path = entry.path().to_path_buf();
+        .collect::<Vec<_>>();
+    let contains = |name: &str| entries.iter().any(|entry| entry.file_name() == name);
+    if contains(KEEP_FILE_NAME) {
+        return None;
+    }
+    let excludes = match contains(IGNORE_FILE_NAME) {
+        true => excludes.nested(dir),
+        false => Arc::clone(excludes),
+    };
+    Some((entries, excludes))
+}
 
-            if skip_dirs.iter().any(|skip| entry_path.starts_with(skip)) {
-                return Some(None);
-            }
+/// Most recent modification of `root` and everything below it that a scan
+/// would walk, see [`FoundFolder::measure_project`].
+fn newest_mtime(
+    root: &Path,
+    excludes: &Arc<Excludes>,
+    filters: &FilterMatcher,
+) -> Option<SystemTime> {
+    let mounts = filters.mounts();
+    let mut newest = root.symlink_metadata().ok()?.modified().ok();
+    let mut pending = vec![(root.to_path_buf(), Arc::clone(excludes))];
+    while let Some((dir, excludes)) = pending.pop() {
+        let Some((entries, excludes)) = read_dir(&dir, &excludes) else {
+            continue;
+        };
+        let dir_metadata = match mounts.is_active() {
+            true => dir.symlink_metadata().ok(),
+            false => None,
+        };
 
-            if entry.file_type().is_dir() && name_matches_target(&entry_path) {
-                skip_dirs.insert(entry_path.clone());
-                Some(Some(entry_path))
-            } else {
-                Some(None)
+        for entry in entries {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let path = entry.path();
+            if excludes.is_excluded(&path, metadata.is_dir()) {
+                continue;
             }
-        })
-        .flatten()
+            if metadata.is_dir() {
+                if filters.is_match(&path)
+                    || dir_metadata
+                        .as_ref()
+                        .is_some_and(|dir| mounts.skips(dir, &metadata).is_some())
+                {
+                    continue;
+                }
+                pending.push((path, Arc::clone(&excludes)));
+            }
+            newest = newest.max(metadata.modified().ok());
+        }
+    }
+    newest
+}
+
+/// Starts the walk at the scan root. The root is never reported itself, even
+/// if a filter matches it, since removing a scanned folder is refused.
+fn scan_root(root: &Path, filters: &FilterMatcher) -> Option<DirScan> {
+    let root = &canonical_root(root);
+    if !root.is_dir() {
+        return None;
+    }
+
+    let mut scan = DirScan {
+        excludes: filters.excludes(),
+        ..DirScan::default()
+    };
+    if excludes::kept_by(root).is_none() {
+        scan.descend.push(root.to_path_buf());
+    }
+    Some(scan)
+}
+
+/// Walks the tree at `path` and returns all folders matched by `filters`.
+///
+/// The walk stops descending at every match, so the contents of matched
+/// folders are only read once, when measuring them. Results are sorted by path.
+pub fn scan_folders(path: impl AsRef<Path>, filters: &FilterMatcher) -> Vec<FoundFolder> {
+    let Some(root) = scan_root(path.as_ref(), filters) else {
+        return Vec::new();
+    };
+
+    let mut found = root
+        .matched
+        .into_iter()
+        .map(|matched| (matched, Arc::clone(&root.excludes)))
+        .collect::<Vec<_>>();
+    let mut pending = root
+        .descend
+        .into_iter()
+        .map(|dir| (dir, Arc::clone(&root.excludes)))
+        .collect::<Vec<_>>();
+    while let Some((dir, excludes)) = pending.pop() {
+        let scan = scan_dir(&dir, &excludes, filters);
+        found.extend(
+            scan.matched
+                .into_iter()
+                .map(|matched| (matched, Arc::clone(&excludes))),
+        );
+        pending.extend(
+            scan.descend
+                .into_iter()
+                .map(|dir| (dir, Arc::clone(&scan.excludes))),
+        );
+    }
+
+    found.sort_unstable_by(|((a, _), _), ((b, _), _)| a.cmp(b));
+    found
+        .into_iter()
+        .filter_map(|(matched, excludes)| measure_match(matched, &excludes, filters).ok())
         .collect()
 }
 
-pub fn remove_folders(paths: Vec<PathBuf>) -> (Vec<PathBuf>, Vec<PathBuf>) {
-    let fold_into_tuple = |(mut deleted, mut errors): (Vec<PathBuf>, Vec<PathBuf>),
-                           (original_path, result_of_removal)| {
-        match result_of_removal {
-            Ok(_) => {
-                deleted.push(original_path);
+/// Shared flag used to stop a running scan early.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Requests the scan to stop. Directories already being read are finished.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Snapshot of a running scan.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanProgress {
+    pub dirs_visited: u64,
+    pub current_path: PathBuf,
+    pub matches: u64,
+    /// Matches left out for being modified more recently than the minimum age.
+    pub too_recent: u64,
+    /// Matches left out for being smaller than the minimum size.
+    pub too_small: u64,
+    /// Mount points not entered because of their filesystem.
+    pub skipped_mounts: Vec<SkippedMount>,
+}
+
+/// Events produced by [`scan_folders_streaming`].
+#[derive(Debug, Clone)]
+pub enum ScanEvent {
+    /// Emitted periodically while the tree is walked.
+    Progress(ScanProgress),
+    /// A matched folder, already measured and above all thresholds.
+    Found(FoundFolder),
+    /// Always the last event of a scan.
+    Finished {
+        progress: ScanProgress,
+        cancelled: bool,
+    },
+}
+
+/// State shared by the walker threads of a parallel scan.
+struct Walk<'a> {
+    filters: &'a FilterMatcher,
+    cancel: &'a CancellationToken,
+    on_match: &'a (dyn Fn(FoundFolder) + Sync),
+    on_progress: &'a (dyn Fn(ScanProgress) + Sync),
+    dirs_visited: AtomicU64,
+    matches: AtomicU64,
+    too_recent: AtomicU64,
+    too_small: AtomicU64,
+    skipped_mounts: Mutex<Vec<SkippedMount>>,
+    /// Folders already reported, which overlapping roots could reach twice.
+    seen: Mutex<HashSet<FolderId, RandomState>>,
+}
+
+impl<'a> Walk<'a> {
+    /// Number of visited directories between two progress reports.
+    const PROGRESS_INTERVAL: u64 = 128;
+
+    fn new(
+        filters: &'a FilterMatcher,
+        cancel: &'a CancellationToken,
+        on_match: &'a (dyn Fn(FoundFolder) + Sync),
+        on_progress: &'a (dyn Fn(ScanProgress) + Sync),
+    ) -> Self {
+        Walk {
+            filters,
+            cancel,
+            on_match,
+            on_progress,
+            dirs_visited: AtomicU64::new(0),
+            matches: AtomicU64::new(0),
+            too_recent: AtomicU64::new(0),
+            too_small: AtomicU64::new(0),
+            skipped_mounts: Mutex::new(Vec::new()),
+            seen: Mutex::new(HashSet::with_hasher(RandomState::new())),
+        }
+    }
+
+    /// Walks everything below `root`, on `pool` if given, otherwise on the global pool.
+    fn run(&self, pool: Option<&rayon::ThreadPool>, root: DirScan) {
+        let walk = || {
+            self.report(root.matched, &root.excludes);
+            rayon::scope(|scope| {
+                for dir in root.descend {
+                    let excludes = Arc::clone(&root.excludes);
+                    scope.spawn(|scope| self.visit(scope, dir, excludes));
+                }
+            })
+        };
+        match pool {
+            Some(pool) => pool.install(walk),
+            None => walk(),
+        }
+    }
+
+    fn visit<'scope>(
+        &'scope self,
+        scope: &rayon::Scope<'scope>,
+        dir: PathBuf,
+        excludes: Arc<Excludes>,
+    ) {
+        if self.cancel.is_cancelled() {
+            return;
+        }
+
+        let scan = scan_dir(&dir, &excludes, self.filters);
+        self.report(scan.matched, &excludes);
+        if !scan.skipped_mounts.is_empty() {
+            self.skipped_mounts
+                .lock()
+                .unwrap()
+                .extend(scan.skipped_mounts);
+        }
+
+        let visited = self.dirs_visited.fetch_add(1, Ordering::Relaxed) + 1;
+        if visited.is_multiple_of(Self::PROGRESS_INTERVAL) {
+            (self.on_progress)(self.progress(dir));
+        }
+
+        for dir in scan.descend {
+            let excludes = Arc::clone(&scan.excludes);
+            scope.spawn(move |scope| self.visit(scope, dir, excludes));
+        }
+    }
+
+    /// Records `folder` as reported, returning `true` if it already was.
+    fn already_seen(&self, folder: &FoundFolder) -> bool {
+        folder
+            .id
+            .is_some_and(|id| !self.seen.lock().unwrap().insert(id))
+    }
+
+    /// Measures matched folders and reports those above the thresholds.
+    /// `excludes` are those in effect for the directory containing them.
+    fn report(&self, matched: Vec<(PathBuf, MatchReason)>, excludes: &Arc<Excludes>) {
+        for matched in matched {
+            if self.cancel.is_cancelled() {
+                return;
             }
-            Err(e) => {
-                eprintln!("{e}");
-                errors.push(original_path);
+            match measure_match(matched, excludes, self.filters) {
+                Ok(folder) if self.already_seen(&folder) => {}
+                Ok(folder) => {
+                    self.matches.fetch_add(1, Ordering::Relaxed);
+                    (self.on_match)(folder);
+                }
+                Err(Skipped::TooRecent) => {
+                    self.too_recent.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(Skipped::TooSmall) => {
+                    self.too_small.fetch_add(1, Ordering::Relaxed);
+                }
             }
         }
-        (deleted, errors)
+    }
+
+    fn progress(&self, current_path: PathBuf) -> ScanProgress {
+        ScanProgress {
+            dirs_visited: self.dirs_visited.load(Ordering::Relaxed),
+            current_path,
+            matches: self.matches.load(Ordering::Relaxed),
+            too_recent: self.too_recent.load(Ordering::Relaxed),
+            too_small: self.too_small.load(Ordering::Relaxed),
+            skipped_mounts: self.skipped_mounts.lock().unwrap().clone(),
+        }
+    }
+}
+
+fn thread_pool(threads: usize) -> Option<rayon::ThreadPool> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .ok()
+}
+
+/// Multi-threaded variant of [`scan_folders`] producing the same results.
+///
+/// Every subdirectory is queued as its own task on a work-stealing pool, so
+/// deep and wide trees keep all `threads` busy. A thread count of `0` uses one
+/// thread per available core.
+pub fn scan_folders_parallel(
+    path: impl AsRef<Path>,
+    filters: &FilterMatcher,
+    threads: usize,
+) -> Vec<FoundFolder> {
+    let Some(root) = scan_root(path.as_ref(), filters) else {
+        return Vec::new();
     };
 
-    let (deleted, errored) = paths
+    let found = Mutex::new(Vec::new());
+    let cancel = CancellationToken::new();
+    let on_match = |folder| found.lock().unwrap().push(folder);
+    Walk::new(filters, &cancel, &on_match, &|_| {}).run(thread_pool(threads).as_ref(), root);
+
+    let mut found = found.into_inner().unwrap();
+    found.sort_unstable_by(|a: &FoundFolder, b| a.path.cmp(&b.path));
+    found
+}
+
+/// Scans in the background, yielding matches as soon as they are measured.
+///
+/// Besides [`ScanEvent::Found`], the returned channel receives periodic
+/// [`ScanEvent::Progress`] events and ends with [`ScanEvent::Finished`]. Once
+/// `cancel` is triggered no new directories are read and no further matches are
+/// reported. Results arrive in discovery order.
+pub fn scan_folders_streaming(
+    path: impl Into<PathBuf>,
+    filters: FilterMatcher,
+    threads: usize,
+    cancel: CancellationToken,
+) -> Receiver<ScanEvent> {
+    scan_roots_streaming(vec![path.into()], filters, threads, cancel)
+}
+
+/// Like [`scan_folders_streaming`], but scans several roots one after another.
+///
+/// Roots nested in other roots are dropped with [`dedupe_roots`], and folders
+/// reachable from several roots, e.g. through bind mounts, are only reported once.
+pub fn scan_roots_streaming(
+    roots: Vec<PathBuf>,
+    filters: FilterMatcher,
+    threads: usize,
+    cancel: CancellationToken,
+) -> Receiver<ScanEvent> {
+    let roots = dedupe_roots(roots);
+    let (sender, receiver) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        let on_match = |folder| {
+            if !cancel.is_cancelled() {
+                let _ = sender.send(ScanEvent::Found(folder));
+            }
+        };
+        let on_progress = |progress| {
+            let _ = sender.send(ScanEvent::Progress(progress));
+        };
+        let walk = Walk::new(&filters, &cancel, &on_match, &on_progress);
+
+        let pool = thread_pool(threads);
+        for root in &roots {
+            if let Some(scan) = scan_root(root, &filters) {
+                walk.run(pool.as_ref(), scan);
+            }
+        }
+
+        let _ = sender.send(ScanEvent::Finished {
+            progress: walk.progress(roots.last().cloned().unwrap_or_default()),
+            cancelled: cancel.is_cancelled(),
+        });
+    });
+
+    receiver
+}
+
+/// Drops roots equal to or nested inside another root, so that no folder is
+/// walked twice. The remaining roots keep their order and are canonicalized,
+/// see [`canonical_root`].
+pub fn dedupe_roots(roots: impl IntoIterator<Item = PathBuf>) -> Vec<PathBuf> {
+    let roots = roots
         .into_iter()
-        .map(|p| (p.clone(), fs::remove_dir_all(p)))
-        .fold((Vec::new(), Vec::new()), fold_into_tuple);
+        .map(|root| canonical_root(&root))
+        .collect::<Vec<_>>();
+
+    roots
+        .iter()
+        .enumerate()
+        .filter(|(i, root)| {
+            !roots
+                .iter()
+                .enumerate()
+                .any(|(j, other)| root.starts_with(other) && (*root != other || j < *i))
+        })
+        .map(|(_, root)| root.clone())
+        .collect()
+}
+
+/// Resolves `root` to an absolute path without `.` or `..`, since path rules
+/// and global excludes are anchored at the filesystem root.
+fn canonical_root(root: &Path) -> PathBuf {
+    fs::canonicalize(root)
+        .or_else(|_| std::path::absolute(root))
+        .unwrap_or_else(|_| root.to_path_buf())
+}
+
+/// Returns the root among `roots` that `path` was found in.
+pub fn root_of<'a>(path: &Path, roots: &'a [PathBuf]) -> Option<&'a PathBuf> {
+    roots.iter().find(|root| path.starts_with(root))
+}
+
+/// Removes every folder allowed by `guard` with `remover`, recording the
+/// outcome of each one. Refused folders are reported instead of removed.
+///
+/// Each folder is validated again right before its removal, so folders that
+/// were swapped for a symlink or another directory since the scan are refused.
+pub fn remove_folders(
+    folders: Vec<FoundFolder>,
+    remover: &dyn Remover,
+    guard: &SafetyGuard,
+) -> RemovalReport {
+    let start = Instant::now();
+    let mut report = RemovalReport::default();
+
+    for folder in folders {
+        if let Err(violation) = guard.check_folder(&folder) {
+            report.refused.push(Refusal { folder, violation });
+            continue;
+        }
+
+        let folder_start = Instant::now();
+        let result = remover.remove(&folder);
+        let bytes_freed = match &result {
+            Ok(Removal::DryRun) => 0,
+            Ok(_) => folder.usage.allocated_bytes,
+            Err(_) => {
+                let remaining = DiskUsage::of(&folder.path).unwrap_or_default();
+                folder
+                    .usage
+                    .allocated_bytes
+                    .saturating_sub(remaining.allocated_bytes)
+            }
+        };
+        report.outcomes.push(RemovalOutcome {
+            folder,
+            result,
+            bytes_freed,
+            elapsed: folder_start.elapsed(),
+        });
+    }
+
+    report.elapsed = start.elapsed();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn write(path: PathBuf, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn sorted_paths(found: Vec<FoundFolder>) -> Vec<PathBuf> {
+        let mut paths = found.into_iter().map(|f| f.path).collect::<Vec<_>>();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn parallel_scan_matches_serial_scan() {
+        let root = tempfile::tempdir().unwrap();
+        for i in 0..24 {
+            let repo = root.path().join(format!("group-{}/repo-{i}", i % 4));
+            write(repo.join("src/nested/file.txt"), "content");
+            match i % 4 {
+                0 => {
+                    write(repo.join("Cargo.toml"), "[package]");
+                    write(repo.join("target/debug/lib.rlib"), "rlib");
+                }
+                1 => {
+                    write(repo.join("package.json"), "{}");
+                    write(repo.join("node_modules/a/node_modules/b/index.js"), "");
+                }
+                2 => write(repo.join("src/__pycache__/mod.pyc"), "pyc"),
+                // Not matched without the marker of its filter.
+                _ => write(repo.join("target/debug/lib.rlib"), "rlib"),
+            }
+        }
+        let filters = Config::default().filters.compile().unwrap();
+
+        let serial = sorted_paths(scan_folders(root.path(), &filters));
+        let parallel = sorted_paths(scan_folders_parallel(root.path(), &filters, 4));
+
+        assert_eq!(serial, parallel);
+        // One match per repo with a marker, none nested in another match.
+        assert_eq!(serial.len(), 18);
+        assert!(!serial.iter().any(|path| path.ends_with("a/node_modules")));
+    }
+
+    #[test]
+    fn matching_root_is_walked_instead_of_reported() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path().join("Cargo.toml"), "[package]");
+        let target = root.path().join("target");
+        write(target.join("tool/package.json"), "{}");
+        write(target.join("tool/node_modules/dep/index.js"), "");
+        let filters = Config::default().filters.compile().unwrap();
+
+        for found in [
+            scan_folders(&target, &filters),
+            scan_folders_parallel(&target, &filters, 2),
+        ] {
+            assert_eq!(sorted_paths(found), [target.join("tool/node_modules")]);
+        }
+    }
+
+    #[test]
+    fn age_covers_the_project() {
+        let root = tempfile::tempdir().unwrap();
+        let modules = root.path().join("node_modules");
+        write(modules.join("dep/index.js"), "");
+        write(root.path().join("package.json"), "{}");
+        let old = SystemTime::now() - std::time::Duration::from_secs(60 * 86400);
+        for path in [modules.join("dep/index.js"), modules.join("dep"), modules] {
+            fs::File::open(path).unwrap().set_modified(old).unwrap();
+        }
+        let filters = Config::default().filters.compile().unwrap();
+
+        // The project is only walked when its age is needed.
+        let found = scan_folders(root.path(), &filters);
+        assert_eq!(found[0].project_mtime, None);
+        assert!(found[0].age().unwrap() > "30d".parse().unwrap());
+
+        let found = scan_folders(root.path(), &filters.with_project_age(true));
+        assert_eq!(found.len(), 1);
+        assert!(found[0].age().unwrap() < "1d".parse().unwrap());
+    }
+
+    #[test]
+    fn project_age_skips_excluded_and_kept_folders() {
+        let root = tempfile::tempdir().unwrap();
+        let project = root.path().join("project");
+        write(project.join("package.json"), "{}");
+        write(project.join("node_modules/dep/index.js"), "");
+        write(project.join(IGNORE_FILE_NAME), "release\n");
+        write(project.join("docs").join(KEEP_FILE_NAME), "");
+        let old = SystemTime::now() - std::time::Duration::from_secs(60 * 86400);
+        let age = |path: &Path| fs::File::open(path).unwrap().set_modified(old).unwrap();
+        for entry in walkdir::WalkDir::new(&project).contents_first(true) {
+            age(entry.unwrap().path());
+        }
+        // Recent changes in folders the scan never enters.
+        for dir in ["release", "docs"] {
+            write(project.join(dir).join("new.txt"), "");
+            age(&project.join(dir));
+        }
+        age(&project);
+        let filters = Config::default()
+            .filters
+            .compile()
+            .unwrap()
+            .with_project_age(true);
 
-    (deleted, errored)
+        let found = scan_folders(root.path(), &filters);
+        assert_eq!(found.len(), 1);
+        assert!(found[0].project_mtime.is_some());
+        assert!(found[0].age().unwrap() > "30d".parse().unwrap());
+    }
 }
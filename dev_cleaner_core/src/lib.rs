@@ -1,7 +1,21 @@
 pub mod config;
+#[cfg(unix)]
+pub mod dirfd;
+pub mod excludes;
+pub mod export;
 pub mod filters;
 pub mod utils;
 pub mod folderscan;
+pub mod mounts;
+pub mod presets;
+pub mod remover;
+pub mod safety;
+pub mod select;
+pub mod sizetree;
+pub mod stats;
+#[cfg(unix)]
+pub mod trash;
+pub mod usage;
 
 #[macro_use]
 pub mod macros;
@@ -0,0 +1,185 @@
+use globset::{Glob, GlobSetBuilder};
+use hashbrown::HashMap;
+use std::{
+    fs::{self, Metadata},
+    io,
+    path::PathBuf,
+};
+
+/// A mounted filesystem as listed in `/proc/self/mountinfo`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mount {
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+    pub source: String,
+    /// Device number, as in the `st_dev` of files on this filesystem.
+    pub device: u64,
+}
+
+/// Filesystem types that are slow, remote or virtual, skipped by default.
+pub const DEFAULT_SKIPPED_FS_TYPES: [&str; 24] = [
+    "fuse.*",
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "9p",
+    "afs",
+    "ceph",
+    "glusterfs",
+    "autofs",
+    "proc",
+    "sysfs",
+    "devtmpfs",
+    "devpts",
+    "cgroup",
+    "cgroup2",
+    "securityfs",
+    "debugfs",
+    "tracefs",
+    "pstore",
+    "bpf",
+    "mqueue",
+    "binfmt_misc",
+];
+
+/// A mount point the scan did not enter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SkippedMount {
+    pub path: PathBuf,
+    pub fs_type: String,
+}
+
+/// Decides which mounted filesystems a scan may walk into.
+#[derive(Debug, Clone, Default)]
+pub struct MountPolicy {
+    same_filesystem: bool,
+    /// Device number and type of every mounted filesystem.
+    fs_types: HashMap<u64, String>,
+    /// Devices whose filesystem type is skipped.
+    skipped: Vec<u64>,
+}
+
+impl MountPolicy {
+    /// Skips every filesystem other than the scanned one if `same_filesystem`
+    /// is set, otherwise those whose type matches one of `skip_fs_types`
+    /// (globs allowed, e.g. `fuse.*`).
+    pub fn new(
+        same_filesystem: bool,
+        skip_fs_types: &[String],
+    ) -> Result<MountPolicy, globset::Error> {
+        let mut builder = GlobSetBuilder::new();
+        for fs_type in skip_fs_types {
+            builder.add(Glob::new(fs_type)?);
+        }
+        let skip = builder.build()?;
+
+        let mounts = mounts().unwrap_or_default();
+        Ok(MountPolicy {
+            same_filesystem,
+            skipped: mounts
+                .iter()
+                .filter(|mount| skip.is_match(&mount.fs_type))
+                .map(|mount| mount.device)
+                .collect(),
+            fs_types: mounts
+                .into_iter()
+                .map(|mount| (mount.device, mount.fs_type))
+                .collect(),
+        })
+    }
+
+    /// Checks that `fs_type` is a valid filesystem type pattern.
+    pub fn validate_fs_type(fs_type: &str) -> Result<(), globset::Error> {
+        Glob::new(fs_type).map(|_| ())
+    }
+
+    /// Returns `false` if the policy can never skip anything.
+    pub fn is_active(&self) -> bool {
+        self.same_filesystem || !self.skipped.is_empty()
+    }
+
+    /// Checks a directory against its parent, returning the filesystem type
+    /// of the mount if the scan must not enter it.
+    pub fn skips(&self, parent: &Metadata, dir: &Metadata) -> Option<String> {
+        let (parent, dir) = (device(parent)?, device(dir)?);
+        if parent == dir || !(self.same_filesystem || self.skipped.contains(&dir)) {
+            return None;
+        }
+        Some(
+            self.fs_types
+                .get(&dir)
+                .cloned()
+                .unwrap_or_else(|| "unknown".to_string()),
+        )
+    }
+}
+
+#[cfg(unix)]
+fn device(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+/// Combines the `major:minor` field of mountinfo like glibc's `makedev`.
+fn makedev(major: u64, minor: u64) -> u64 {
+    ((major & 0xffff_f000) << 32)
+        | ((major & 0x0000_0fff) << 8)
+        | ((minor & 0xffff_ff00) << 12)
+        | (minor & 0x0000_00ff)
+}
+
+/// Reads the mounts of the current process. Fails on systems without `/proc`.
+pub fn mounts() -> io::Result<Vec<Mount>> {
+    fs::read_to_string("/proc/self/mountinfo").map(|info| parse_mountinfo(&info))
+}
+
+/// Parses lines of the form
+/// `36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw,errors=continue`,
+/// skipping malformed ones.
+fn parse_mountinfo(info: &str) -> Vec<Mount> {
+    info.lines()
+        .filter_map(|line| {
+            let (mount, filesystem) = line.split_once(" - ")?;
+            let mut fields = mount.split(' ');
+            let (major, minor) = fields.nth(2)?.split_once(':')?;
+            let mount_point = fields.nth(1)?;
+            let mut filesystem = filesystem.split(' ');
+            Some(Mount {
+                mount_point: PathBuf::from(unescape(mount_point)),
+                fs_type: filesystem.next()?.to_string(),
+                source: unescape(filesystem.next()?),
+                device: makedev(major.parse().ok()?, minor.parse().ok()?),
+            })
+        })
+        .collect()
+}
+
+/// Decodes the `\NNN` octal escapes used for spaces, tabs and backslashes.
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes.get(i + 1..i + 4).filter(|_| bytes[i] == b'\\');
+        match escape
+            .and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok())
+        {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 4;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
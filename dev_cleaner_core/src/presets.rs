@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr, sync::LazyLock};
+
+use crate::filters::{Filter, FilterRule, Marker, MatchReason};
+
+/// The filters of every preset, built once for [`Preset::of`].
+static PRESET_FILTERS: LazyLock<Vec<(Preset, Filter)>> = LazyLock::new(|| {
+    Preset::ALL
+        .into_iter()
+        .flat_map(|preset| preset.filters().into_iter().map(move |f| (preset, f)))
+        .collect()
+});
+
+/// A named bundle of filters for the build and cache folders of one ecosystem.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    Rust,
+    Node,
+    Python,
+    Jvm,
+    Dotnet,
+    Elixir,
+    Haskell,
+    Terraform,
+    Zig,
+    Cmake,
+    Swift,
+    Dart,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 12] = [
+        Preset::Rust,
+        Preset::Node,
+        Preset::Python,
+        Preset::Jvm,
+        Preset::Dotnet,
+        Preset::Elixir,
+        Preset::Haskell,
+        Preset::Terraform,
+        Preset::Zig,
+        Preset::Cmake,
+        Preset::Swift,
+        Preset::Dart,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Rust => "rust",
+            Preset::Node => "node",
+            Preset::Python => "python",
+            Preset::Jvm => "jvm",
+            Preset::Dotnet => "dotnet",
+            Preset::Elixir => "elixir",
+            Preset::Haskell => "haskell",
+            Preset::Terraform => "terraform",
+            Preset::Zig => "zig",
+            Preset::Cmake => "cmake",
+            Preset::Swift => "swift",
+            Preset::Dart => "dart",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Preset::Rust => "Cargo build output",
+            Preset::Node => "npm dependencies and bundler caches",
+            Preset::Python => "bytecode, tox and linter caches",
+            Preset::Jvm => "Gradle and Maven build output",
+            Preset::Dotnet => "MSBuild bin and obj folders",
+            Preset::Elixir => "Mix build output and dependencies",
+            Preset::Haskell => "Stack and Cabal build output",
+            Preset::Terraform => "downloaded providers and modules",
+            Preset::Zig => "Zig build cache and output",
+            Preset::Cmake => "CLion style CMake build folders",
+            Preset::Swift => "Swift Package Manager build output",
+            Preset::Dart => "Dart and Flutter tool caches and builds",
+        }
+    }
+
+    /// The preset providing `filter`, if any.
+    pub fn of(filter: &Filter) -> Option<Preset> {
+        PRESET_FILTERS
+            .iter()
+            .find(|(_, f)| f == filter)
+            .map(|(preset, _)| *preset)
+    }
+
+    /// The ecosystem of a match: the name of the preset providing its filter,
+    /// `cache` for `CACHEDIR.TAG` matches and `custom` for other filters.
+    pub fn ecosystem(reason: &MatchReason) -> &'static str {
+        match reason {
+            MatchReason::Filter(filter) => Preset::of(filter).map_or("custom", Preset::name),
+            MatchReason::CacheDirTag => "cache",
+        }
+    }
+
+    /// The filters enabled by this preset. Every rule requires a marker file
+    /// of the ecosystem, except for folders only ever created by a tool.
+    pub fn filters(self) -> Vec<Filter> {
+        let name = |name: &str| Filter::new(FilterRule::Name(name.to_string()));
+        let next_to = |name: &str, markers: &[&str]| {
+            Filter::new(FilterRule::Name(name.to_string()))
+                .with_markers(markers.iter().map(|m| Marker::Sibling(m.to_string())))
+        };
+        let gradle = [
+            "build.gradle",
+            "build.gradle.kts",
+            "settings.gradle",
+            "settings.gradle.kts",
+        ];
+        let msbuild = ["*.csproj", "*.fsproj", "*.vbproj", "*.sln"];
+
+        match self {
+            Preset::Rust => vec![next_to("target", &["Cargo.toml"])],
+            Preset::Node => vec![
+                next_to("node_modules", &["package.json"]),
+                next_to(".next", &["package.json"]),
+                next_to(".nuxt", &["package.json"]),
+                next_to(".svelte-kit", &["package.json"]),
+                next_to(".turbo", &["package.json"]),
+                name(".parcel-cache"),
+            ],
+            Preset::Python => vec![
+                name("__pycache__").with_markers([Marker::Contains("*.pyc".to_string())]),
+                next_to(
+                    ".tox",
+                    &["tox.ini", "pyproject.toml", "setup.py", "setup.cfg"],
+                ),
+                name(".mypy_cache"),
+                name(".pytest_cache"),
+                name(".ruff_cache"),
+            ],
+            Preset::Jvm => vec![
+                next_to("build", &gradle),
+                next_to(".gradle", &gradle),
+                next_to("target", &["pom.xml"]),
+            ],
+            Preset::Dotnet => vec![next_to("bin", &msbuild), next_to("obj", &msbuild)],
+            Preset::Elixir => vec![
+                next_to("_build", &["mix.exs"]),
+                next_to("deps", &["mix.exs"]),
+            ],
+            Preset::Haskell => vec![
+                next_to(".stack-work", &["stack.yaml"]),
+                next_to("dist-newstyle", &["cabal.project", "*.cabal"]),
+            ],
+            Preset::Terraform => vec![next_to(".terraform", &["*.tf"])],
+            Preset::Zig => vec![
+                next_to("zig-cache", &["build.zig"]),
+                next_to(".zig-cache", &["build.zig"]),
+                next_to("zig-out", &["build.zig"]),
+            ],
+            Preset::Cmake => vec![
+                Filter::new(FilterRule::Glob("cmake-build-*".to_string()))
+                    .with_markers([Marker::Sibling("CMakeLists.txt".to_string())]),
+            ],
+            Preset::Swift => vec![next_to(".build", &["Package.swift"])],
+            Preset::Dart => vec![
+                next_to(".dart_tool", &["pubspec.yaml"]),
+                next_to("build", &["pubspec.yaml"]),
+            ],
+        }
+    }
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        Preset::ALL
+            .into_iter()
+            .find(|preset| preset.name() == s)
+            .ok_or_else(|| {
+                let names = Preset::ALL.map(Preset::name);
+                format!("unknown preset `{s}`, use one of {}", names.join(", "))
+            })
+    }
+}
+
+impl Display for Preset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
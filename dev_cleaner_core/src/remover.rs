@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, io, path::PathBuf, str::FromStr, time::Duration};
+#[cfg(not(unix))]
+use std::{fs, path::Path};
+
+use crate::{
+    folderscan::{FolderId, FoundFolder},
+    safety::Violation,
+};
+
+/// What happened to a removed folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Removal {
+    Deleted,
+    Trashed {
+        location: PathBuf,
+    },
+    /// Nothing was touched, see [`DryRun`].
+    DryRun,
+}
+
+/// Why a folder could not be removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemovalError {
+    pub kind: io::ErrorKind,
+    /// The entry that failed, which may be a file deep inside the folder.
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl RemovalError {
+    pub fn new(path: impl Into<PathBuf>, error: io::Error) -> RemovalError {
+        RemovalError {
+            kind: error.kind(),
+            path: path.into(),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl Display for RemovalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+impl std::error::Error for RemovalError {}
+
+/// Strategy used by [`crate::folderscan::remove_folders`] to get rid of a folder.
+///
+/// Implementations must not follow a symlink found in place of the folder,
+/// and should refuse folders whose [`FoundFolder::id`] no longer matches.
+pub trait Remover {
+    fn remove(&self, folder: &FoundFolder) -> Result<Removal, RemovalError>;
+}
+
+/// Deletes folders permanently.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Delete;
+
+impl Remover for Delete {
+    #[cfg(unix)]
+    fn remove(&self, folder: &FoundFolder) -> Result<Removal, RemovalError> {
+        crate::dirfd::remove_folder(&folder.path, folder.id).map(|_| Removal::Deleted)
+    }
+
+    #[cfg(not(unix))]
+    fn remove(&self, folder: &FoundFolder) -> Result<Removal, RemovalError> {
+        remove_tree(&folder.path).map(|_| Removal::Deleted)
+    }
+}
+
+/// Like [`fs::remove_dir_all`], but reports which entry could not be removed.
+#[cfg(not(unix))]
+fn remove_tree(path: &Path) -> Result<(), RemovalError> {
+    let error = |e| RemovalError::new(path, e);
+
+    if !path.symlink_metadata().map_err(error)?.is_dir() {
+        return fs::remove_file(path).map_err(error);
+    }
+
+    for entry in fs::read_dir(path).map_err(error)? {
+        let entry = entry.map_err(error)?;
+        let entry_path = entry.path();
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => remove_tree(&entry_path)?,
+            Ok(_) => fs::remove_file(&entry_path).map_err(|e| RemovalError::new(&entry_path, e))?,
+            Err(e) => return Err(RemovalError::new(&entry_path, e)),
+        }
+    }
+    fs::remove_dir(path).map_err(error)
+}
+
+/// Moves folders to the freedesktop.org trash.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Trash;
+
+impl Remover for Trash {
+    #[cfg(unix)]
+    fn remove(&self, folder: &FoundFolder) -> Result<Removal, RemovalError> {
+        crate::trash::move_to_trash(&folder.path, folder.id)
+            .map(|location| Removal::Trashed { location })
+            .map_err(|e| RemovalError::new(&folder.path, e))
+    }
+
+    #[cfg(not(unix))]
+    fn remove(&self, folder: &FoundFolder) -> Result<Removal, RemovalError> {
+        Err(RemovalError::new(
+            &folder.path,
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the trash is only supported on unix systems",
+            ),
+        ))
+    }
+}
+
+/// Removes nothing, only checks that the folder is still there and unchanged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DryRun;
+
+impl Remover for DryRun {
+    fn remove(&self, folder: &FoundFolder) -> Result<Removal, RemovalError> {
+        let error = |e| RemovalError::new(&folder.path, e);
+        let metadata = folder.path.symlink_metadata().map_err(error)?;
+        if !metadata.is_dir() {
+            return Err(error(io::Error::new(
+                io::ErrorKind::NotADirectory,
+                "not a directory",
+            )));
+        }
+        match folder.id.is_none() || folder.id == FolderId::of(&metadata) {
+            true => Ok(Removal::DryRun),
+            false => Err(error(io::Error::other(
+                "replaced by another folder since the scan",
+            ))),
+        }
+    }
+}
+
+/// Result of removing a single folder.
+#[derive(Debug, Clone)]
+pub struct RemovalOutcome {
+    pub folder: FoundFolder,
+    pub result: Result<Removal, RemovalError>,
+    /// Allocated bytes gone from the folder's location. Failed removals may
+    /// still have freed part of the folder. Always `0` for a dry run.
+    pub bytes_freed: u64,
+    pub elapsed: Duration,
+}
+
+impl RemovalOutcome {
+    pub fn is_removed(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// A folder the [`crate::safety::SafetyGuard`] did not allow to be removed.
+#[derive(Debug, Clone)]
+pub struct Refusal {
+    pub folder: FoundFolder,
+    pub violation: Violation,
+}
+
+/// Per-folder outcomes of [`crate::folderscan::remove_folders`].
+#[derive(Debug, Clone, Default)]
+pub struct RemovalReport {
+    pub outcomes: Vec<RemovalOutcome>,
+    /// Folders that were never handed to the remover.
+    pub refused: Vec<Refusal>,
+    pub elapsed: Duration,
+}
+
+impl RemovalReport {
+    pub fn removed(&self) -> impl Iterator<Item = &RemovalOutcome> {
+        self.outcomes.iter().filter(|o| o.is_removed())
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &RemovalOutcome> {
+        self.outcomes.iter().filter(|o| !o.is_removed())
+    }
+
+    pub fn bytes_freed(&self) -> u64 {
+        self.outcomes.iter().map(|o| o.bytes_freed).sum()
+    }
+
+    /// Allocated bytes of all successfully removed folders, as measured by the scan.
+    pub fn bytes_removed(&self) -> u64 {
+        self.removed().map(|o| o.folder.usage.allocated_bytes).sum()
+    }
+}
+
+/// The configurable choice of [`Remover`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RemovalMethod {
+    #[default]
+    Delete,
+    Trash,
+}
+
+impl RemovalMethod {
+    pub fn remover(self) -> Box<dyn Remover> {
+        match self {
+            RemovalMethod::Delete => Box::new(Delete),
+            RemovalMethod::Trash => Box::new(Trash),
+        }
+    }
+}
+
+impl FromStr for RemovalMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "delete" => Ok(RemovalMethod::Delete),
+            "trash" => Ok(RemovalMethod::Trash),
+            other => Err(format!(
+                "unknown removal method `{other}`, use delete or trash"
+            )),
+        }
+    }
+}
+
+impl Display for RemovalMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RemovalMethod::Delete => write!(f, "delete"),
+            RemovalMethod::Trash => write!(f, "trash"),
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use std::{
+    env,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    excludes::{self, KEEP_FILE_NAME},
+    filters::FilterMatcher,
+    folderscan::{FolderId, FoundFolder},
+    mounts,
+};
+
+/// Why a folder was refused by the [`SafetyGuard`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// The folder is, or contains, the filesystem root, the home directory or an XDG directory.
+    Essential(PathBuf),
+    /// The folder is, or contains, a mount point.
+    MountPoint(PathBuf),
+    /// The folder is, contains or lies inside a path protected in the configuration.
+    Protected(PathBuf),
+    /// The folder is, or contains, one of the scanned roots.
+    Root(PathBuf),
+    /// The folder does not lie under any of the scanned roots.
+    OutsideRoots,
+    /// The folder was replaced by a symlink since the scan.
+    Symlink,
+    /// The folder was replaced by something that is not a directory since the scan.
+    NotADirectory,
+    /// The folder was replaced by a different directory since the scan.
+    Replaced,
+    /// The folder is no longer matched by any filter.
+    NoLongerMatches,
+    /// The folder lies inside a directory with a `.devcleaner-keep` marker.
+    Kept(PathBuf),
+    /// The folder, or one of its parents, is excluded by an ignore file.
+    Excluded,
+    /// The folder no longer exists.
+    Missing,
+    /// The folder is now matched by a different rule than the one planned.
+    RuleChanged(String),
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::Essential(p) => write!(f, "would remove essential path {}", p.display()),
+            Violation::MountPoint(p) => write!(f, "would remove mount point {}", p.display()),
+            Violation::Protected(p) => write!(f, "protected by {}", p.display()),
+            Violation::Root(p) => write!(f, "would remove scanned folder {}", p.display()),
+            Violation::OutsideRoots => write!(f, "outside of the scanned folders"),
+            Violation::Symlink => write!(f, "replaced by a symlink since the scan"),
+            Violation::NotADirectory => write!(f, "no longer a directory"),
+            Violation::Replaced => write!(f, "replaced by another folder since the scan"),
+            Violation::NoLongerMatches => write!(f, "no longer matches any filter"),
+            Violation::Kept(p) => write!(f, "kept by {}", p.join(KEEP_FILE_NAME).display()),
+            Violation::Excluded => write!(f, "excluded by an ignore file"),
+            Violation::Missing => write!(f, "no longer exists"),
+            Violation::RuleChanged(rule) => write!(f, "now matched by {rule}"),
+        }
+    }
+}
+
+/// Last line of defence against misconfigured filters, checked right before removal.
+#[derive(Debug, Clone, Default)]
+pub struct SafetyGuard {
+    essential: Vec<PathBuf>,
+    mount_points: Vec<PathBuf>,
+    protected: Vec<PathBuf>,
+    roots: Vec<PathBuf>,
+    filters: Option<FilterMatcher>,
+}
+
+impl SafetyGuard {
+    /// Creates a guard only allowing removals below `roots`, with the built-in deny list.
+    pub fn new(roots: impl IntoIterator<Item = PathBuf>) -> SafetyGuard {
+        SafetyGuard {
+            essential: essential_paths(),
+            mount_points: mounts::mounts()
+                .map(|mounts| mounts.into_iter().map(|m| m.mount_point).collect())
+                .unwrap_or_default(),
+            protected: Vec::new(),
+            roots: roots.into_iter().map(|root| canonical(&root)).collect(),
+            filters: None,
+        }
+    }
+
+    /// Adds user configured paths that must never be removed, nor anything inside them.
+    pub fn with_protected(self, protected: impl IntoIterator<Item = PathBuf>) -> Self {
+        SafetyGuard {
+            protected: protected.into_iter().map(|p| canonical(&p)).collect(),
+            ..self
+        }
+    }
+
+    /// Requires folders to still be matched by `filters` when they are removed.
+    pub fn with_filters(self, filters: FilterMatcher) -> Self {
+        SafetyGuard {
+            filters: Some(filters),
+            ..self
+        }
+    }
+
+    /// Checks whether the folder at `path` may be removed.
+    pub fn check(&self, path: &Path) -> Result<(), Violation> {
+        let path = resolve(path);
+        // Removing an ancestor of a path removes the path as well
+        let removes = |p: &PathBuf| p.starts_with(&path);
+
+        if let Some(p) = self.essential.iter().find(|p| removes(p)) {
+            return Err(Violation::Essential(p.clone()));
+        }
+        if let Some(p) = self.mount_points.iter().find(|p| removes(p)) {
+            return Err(Violation::MountPoint(p.clone()));
+        }
+        if let Some(p) = self
+            .protected
+            .iter()
+            .find(|p| removes(p) || path.starts_with(p))
+        {
+            return Err(Violation::Protected(p.clone()));
+        }
+        if let Some(root) = self.roots.iter().find(|root| removes(root)) {
+            return Err(Violation::Root(root.clone()));
+        }
+        if !self.roots.iter().any(|root| path.starts_with(root)) {
+            return Err(Violation::OutsideRoots);
+        }
+        Ok(())
+    }
+
+    /// Like [`SafetyGuard::check`], but also verifies that `folder` is still
+    /// the directory found by the scan. Folders that disappeared are allowed,
+    /// removing them fails on its own.
+    pub fn check_folder(&self, folder: &FoundFolder) -> Result<(), Violation> {
+        self.check(&folder.path)?;
+        if let Some(dir) = excludes::kept_by(&folder.path) {
+            return Err(Violation::Kept(dir));
+        }
+        if let Some(filters) = &self.filters
+            && filters.excludes().along(&folder.path).is_none()
+        {
+            return Err(Violation::Excluded);
+        }
+
+        let Ok(metadata) = folder.path.symlink_metadata() else {
+            return Ok(());
+        };
+        if metadata.is_symlink() {
+            return Err(Violation::Symlink);
+        }
+        if !metadata.is_dir() {
+            return Err(Violation::NotADirectory);
+        }
+        if folder.id.is_some() && folder.id != FolderId::of(&metadata) {
+            return Err(Violation::Replaced);
+        }
+        if let Some(filters) = &self.filters
+            && !filters.is_match(&folder.path)
+        {
+            return Err(Violation::NoLongerMatches);
+        }
+        Ok(())
+    }
+}
+
+/// Makes `path` absolute and resolves symlinks in its parents, but not in the
+/// last component, which is what a removal acts on.
+fn resolve(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => fs::canonicalize(parent)
+            .map(|parent| parent.join(name))
+            .unwrap_or(path),
+        _ => path,
+    }
+}
+
+/// Fully resolves `path` if it exists, as needed for folders that contain others.
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| resolve(path))
+}
+
+/// The filesystem root, the home directory and the XDG base and user directories.
+fn essential_paths() -> Vec<PathBuf> {
+    let Some(home) = env::var_os("HOME").map(PathBuf::from) else {
+        return vec![PathBuf::from("/")];
+    };
+
+    let xdg = |var: &str, default: &str| {
+        env::var_os(var)
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .unwrap_or_else(|| home.join(default))
+    };
+    let config_home = xdg("XDG_CONFIG_HOME", ".config");
+    let mut paths = vec![
+        PathBuf::from("/"),
+        home.clone(),
+        config_home.clone(),
+        xdg("XDG_DATA_HOME", ".local/share"),
+        xdg("XDG_STATE_HOME", ".local/state"),
+        xdg("XDG_CACHE_HOME", ".cache"),
+        home.join(".local/bin"),
+    ];
+
+    // Lines like `XDG_DOCUMENTS_DIR="$HOME/Documents"`
+    if let Ok(user_dirs) = fs::read_to_string(config_home.join("user-dirs.dirs")) {
+        paths.extend(user_dirs.lines().filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            if !key.trim().starts_with("XDG_") {
+                return None;
+            }
+            let value = value.trim().trim_matches('"');
+            match value.strip_prefix("$HOME") {
+                Some(rest) => Some(home.join(rest.trim_start_matches('/'))),
+                None => Some(PathBuf::from(value)).filter(|p| p.is_absolute()),
+            }
+        }));
+    }
+
+    paths.into_iter().map(|p| canonical(&p)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_essential_paths_and_roots() {
+        let root = tempfile::tempdir().unwrap();
+        let guard = SafetyGuard::new([PathBuf::from("/"), root.path().to_path_buf()]);
+
+        if let Some(home) = env::var_os("HOME") {
+            assert!(matches!(
+                guard.check(Path::new(&home)),
+                Err(Violation::Essential(_))
+            ));
+        }
+        assert!(matches!(
+            guard.check(Path::new("/")),
+            Err(Violation::Essential(_))
+        ));
+        assert!(matches!(guard.check(root.path()), Err(Violation::Root(_))));
+        assert_eq!(guard.check(&root.path().join("target")), Ok(()));
+    }
+
+    #[cfg(unix)]
+    mod swapped {
+        use super::*;
+        use crate::{
+            config::Config,
+            folderscan::{remove_folders, scan_folders},
+            remover::{Delete, Remover},
+        };
+
+        /// Scans a project with a `target` folder, then lets `swap` replace it.
+        fn scan_and_swap(swap: impl FnOnce(&Path)) -> (tempfile::TempDir, Vec<FoundFolder>) {
+            let root = tempfile::tempdir().unwrap();
+            fs::write(root.path().join("Cargo.toml"), "[package]").unwrap();
+            fs::create_dir_all(root.path().join("target/debug")).unwrap();
+            let filters = Config::default().filters.compile().unwrap();
+            let found = scan_folders(root.path(), &filters);
+            assert_eq!(found.len(), 1);
+
+            swap(&root.path().join("target"));
+            (root, found)
+        }
+
+        #[test]
+        fn refuses_folder_swapped_for_symlink() {
+            let outside = tempfile::tempdir().unwrap();
+            fs::write(outside.path().join("precious"), "data").unwrap();
+            let (root, found) = scan_and_swap(|target| {
+                fs::remove_dir_all(target).unwrap();
+                std::os::unix::fs::symlink(outside.path(), target).unwrap();
+            });
+
+            let guard = SafetyGuard::new([root.path().to_path_buf()]);
+            let report = remove_folders(found.clone(), &Delete, &guard);
+            assert_eq!(report.refused.len(), 1);
+            assert_eq!(report.refused[0].violation, Violation::Symlink);
+
+            // Even past the guard the remover does not follow the symlink.
+            assert!(Delete.remove(&found[0]).is_err());
+            assert!(outside.path().join("precious").exists());
+        }
+
+        #[test]
+        fn refuses_folder_swapped_for_another_directory() {
+            let (root, found) = scan_and_swap(|target| {
+                fs::rename(target, target.with_file_name("moved")).unwrap();
+                fs::create_dir(target).unwrap();
+                fs::write(target.join("precious"), "data").unwrap();
+            });
+
+            let guard = SafetyGuard::new([root.path().to_path_buf()]);
+            let report = remove_folders(found.clone(), &Delete, &guard);
+            assert_eq!(report.refused.len(), 1);
+            assert_eq!(report.refused[0].violation, Violation::Replaced);
+
+            assert!(Delete.remove(&found[0]).is_err());
+            assert!(root.path().join("target/precious").exists());
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::{cmp::Ordering, str::FromStr};
+
+use crate::{
+    folderscan::FoundFolder,
+    usage::{Age, ByteSize},
+};
+
+/// An expression choosing found folders without a prompt, e.g. `size>1G && age>14d`.
+///
+/// Each condition compares `size`, `age` or `files` using `<`, `<=`, `>`, `>=`,
+/// `=` or `!=`, or `rule`, `name` or `path` using `=`, `!=` or `~` (contains).
+/// Conditions are combined with `&&` and `||`, where `&&` binds tighter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    /// Alternatives joined by `||`, each a list of conditions joined by `&&`.
+    alternatives: Vec<Vec<Condition>>,
+}
+
+/// A single comparison of a [`Selection`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    /// Space allocated by the folder.
+    Size(Comparison, ByteSize),
+    /// Time since the folder or its project was last modified.
+    Age(Comparison, Age),
+    /// Number of files inside the folder.
+    Files(Comparison, u64),
+    /// The filter rule that matched, as shown in the results.
+    Rule(TextMatch, String),
+    /// The name of the folder.
+    Name(TextMatch, String),
+    /// The full path of the folder.
+    Path(TextMatch, String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextMatch {
+    Equal,
+    NotEqual,
+    Contains,
+}
+
+impl Selection {
+    /// Returns `true` if `folder` satisfies the expression.
+    pub fn matches(&self, folder: &FoundFolder) -> bool {
+        self.alternatives
+            .iter()
+            .any(|conditions| conditions.iter().all(|c| c.matches(folder)))
+    }
+
+    /// Returns `true` if any condition compares the age of folders.
+    pub fn uses_age(&self) -> bool {
+        self.alternatives
+            .iter()
+            .flatten()
+            .any(|condition| matches!(condition, Condition::Age(..)))
+    }
+}
+
+impl Condition {
+    /// Operators in the order they are tried, longer ones first.
+    const OPERATORS: [&'static str; 8] = ["<=", ">=", "==", "!=", "<", ">", "=", "~"];
+
+    pub fn matches(&self, folder: &FoundFolder) -> bool {
+        match self {
+            Condition::Size(cmp, size) => cmp.holds(folder.usage.allocated_bytes.cmp(&size.0)),
+            Condition::Age(cmp, age) => folder.age().is_some_and(|a| cmp.holds(a.cmp(age))),
+            Condition::Files(cmp, files) => cmp.holds(folder.usage.file_count.cmp(files)),
+            Condition::Rule(text, value) => text.holds(&folder.reason.to_string(), value),
+            Condition::Name(text, value) => folder
+                .path
+                .file_name()
+                .is_some_and(|name| text.holds(&name.to_string_lossy(), value)),
+            Condition::Path(text, value) => text.holds(&folder.path.to_string_lossy(), value),
+        }
+    }
+}
+
+impl Comparison {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Less => ordering.is_lt(),
+            Comparison::LessOrEqual => ordering.is_le(),
+            Comparison::Greater => ordering.is_gt(),
+            Comparison::GreaterOrEqual => ordering.is_ge(),
+            Comparison::Equal => ordering.is_eq(),
+            Comparison::NotEqual => ordering.is_ne(),
+        }
+    }
+}
+
+impl TextMatch {
+    fn holds(self, text: &str, value: &str) -> bool {
+        match self {
+            TextMatch::Equal => text == value,
+            TextMatch::NotEqual => text != value,
+            TextMatch::Contains => text.contains(value),
+        }
+    }
+}
+
+impl FromStr for Selection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let alternatives = s
+            .split("||")
+            .map(|alternative| alternative.split("&&").map(str::parse).collect())
+            .collect::<Result<Vec<Vec<Condition>>, _>>()?;
+        Ok(Selection { alternatives })
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("empty condition in selection".to_string());
+        }
+        let split = s
+            .find(['<', '>', '=', '!', '~'])
+            .ok_or_else(|| format!("missing operator in `{s}`, expected e.g. size>1G"))?;
+        let (field, rest) = s.split_at(split);
+        let operator = Self::OPERATORS
+            .into_iter()
+            .find(|op| rest.starts_with(op))
+            .ok_or_else(|| format!("unknown operator in `{s}`"))?;
+        let value = rest[operator.len()..].trim();
+
+        let comparison = || match operator {
+            "<" => Ok(Comparison::Less),
+            "<=" => Ok(Comparison::LessOrEqual),
+            ">" => Ok(Comparison::Greater),
+            ">=" => Ok(Comparison::GreaterOrEqual),
+            "=" | "==" => Ok(Comparison::Equal),
+            "!=" => Ok(Comparison::NotEqual),
+            _ => Err(format!("`{operator}` only applies to rule, name and path")),
+        };
+        let text = || match operator {
+            "=" | "==" => Ok(TextMatch::Equal),
+            "!=" => Ok(TextMatch::NotEqual),
+            "~" => Ok(TextMatch::Contains),
+            _ => Err(format!("`{operator}` only applies to size, age and files")),
+        };
+
+        match field.trim() {
+            "size" => Ok(Condition::Size(comparison()?, value.parse()?)),
+            "age" => Ok(Condition::Age(comparison()?, value.parse()?)),
+            "files" => Ok(Condition::Files(
+                comparison()?,
+                value
+                    .parse()
+                    .map_err(|_| format!("invalid file count `{value}`"))?,
+            )),
+            "rule" => Ok(Condition::Rule(text()?, value.to_string())),
+            "name" => Ok(Condition::Name(text()?, value.to_string())),
+            "path" => Ok(Condition::Path(text()?, value.to_string())),
+            field => Err(format!(
+                "unknown field `{field}`, use size, age, files, rule, name or path"
+            )),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use ahash::RandomState;
+use hashbrown::HashSet;
+use rayon::prelude::*;
+use std::{
+    borrow::Cow,
+    fs::{self, Metadata},
+    io,
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use crate::{
+    filters::{FilterMatcher, MatchReason},
+    folderscan::FolderId,
+    usage::{self, DiskUsage},
+};
+
+/// A directory annotated with the disk usage of everything below it, for
+/// finding what takes up space beyond the configured filters.
+#[derive(Debug, Clone)]
+pub struct SizeTree {
+    pub path: PathBuf,
+    /// Usage of the whole subtree, including the directory itself.
+    pub usage: DiskUsage,
+    /// Subdirectories, largest first.
+    pub children: Vec<SizeTree>,
+    /// Why the directory is matched by the filters, if it is.
+    pub reason: Option<MatchReason>,
+}
+
+impl SizeTree {
+    /// Measures the canonical form of `path` and every directory below it
+    /// without following symlinks or leaving its filesystem. Hard-linked
+    /// files are only counted once, unreadable entries are skipped.
+    pub fn build(path: impl Into<PathBuf>, filters: &FilterMatcher) -> io::Result<SizeTree> {
+        let path = fs::canonicalize(path.into())?;
+        let metadata = path.symlink_metadata()?;
+        if !metadata.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotADirectory,
+                "can only explore directories",
+            ));
+        }
+        let device = FolderId::of(&metadata).map(|id| id.dev);
+        let seen_links = Mutex::new(HashSet::with_hasher(RandomState::new()));
+        Ok(Self::build_dir(
+            path,
+            &metadata,
+            device,
+            filters,
+            &seen_links,
+        ))
+    }
+
+    fn build_dir(
+        path: PathBuf,
+        metadata: &Metadata,
+        device: Option<u64>,
+        filters: &FilterMatcher,
+        seen_links: &Mutex<HashSet<(u64, u64), RandomState>>,
+    ) -> SizeTree {
+        let mut usage = DiskUsage::default();
+        usage.add_entry(metadata);
+
+        let mut subdirs = Vec::new();
+        for entry in fs::read_dir(&path).into_iter().flatten().flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                if device.is_none() || FolderId::of(&metadata).map(|id| id.dev) == device {
+                    subdirs.push((entry.path(), metadata));
+                }
+                continue;
+            }
+            if let Some(id) = usage::hard_link_id(&metadata)
+                && !seen_links.lock().unwrap().insert(id)
+            {
+                continue;
+            }
+            usage.add_entry(&metadata);
+        }
+
+        let mut children = subdirs
+            .into_par_iter()
+            .map(|(path, metadata)| Self::build_dir(path, &metadata, device, filters, seen_links))
+            .collect::<Vec<_>>();
+        usage += children.iter().map(|child| child.usage).sum::<DiskUsage>();
+        children.sort_by_key(|child| std::cmp::Reverse(child.usage.allocated_bytes));
+
+        SizeTree {
+            reason: filters.reason(&path),
+            path,
+            usage,
+            children,
+        }
+    }
+
+    /// The directory name, or the whole path for the root of the filesystem.
+    pub fn name(&self) -> Cow<'_, str> {
+        match self.path.file_name() {
+            Some(name) => name.to_string_lossy(),
+            None => self.path.to_string_lossy(),
+        }
+    }
+
+    /// The node at `path`, given as child indices from this one.
+    pub fn get(&self, path: &[usize]) -> Option<&SizeTree> {
+        path.iter().try_fold(self, |node, i| node.children.get(*i))
+    }
+
+    /// Matches every directory of the tree against `filters` again, e.g.
+    /// after a filter was added.
+    pub fn rematch(&mut self, filters: &FilterMatcher) {
+        self.reason = filters.reason(&self.path);
+        self.children
+            .par_iter_mut()
+            .for_each(|child| child.rematch(filters));
+    }
+
+    /// Usage of all matched directories in the tree, without counting
+    /// matches nested in other matches twice.
+    pub fn matched_usage(&self) -> DiskUsage {
+        match self.reason {
+            Some(_) => self.usage,
+            None => self.children.iter().map(SizeTree::matched_usage).sum(),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use ahash::RandomState;
+use hashbrown::HashMap;
+use std::{
+    hash::Hash,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    folderscan::{FoundFolder, root_of},
+    presets::Preset,
+    usage::DiskUsage,
+};
+
+/// Reclaimable space of found folders, broken down in several ways.
+#[derive(Debug, Clone, Default)]
+pub struct ScanStats {
+    pub total: DiskUsage,
+    pub folders: usize,
+    pub by_rule: Vec<Bucket<String>>,
+    pub by_ecosystem: Vec<Bucket<&'static str>>,
+    pub by_root: Vec<Bucket<PathBuf>>,
+    pub by_project: Vec<Bucket<PathBuf>>,
+    /// The found folders, largest first.
+    pub largest: Vec<FoundFolder>,
+}
+
+/// Found folders sharing a rule, ecosystem, root or project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bucket<K> {
+    pub key: K,
+    pub folders: usize,
+    pub usage: DiskUsage,
+}
+
+impl ScanStats {
+    /// Sums up `folders` found below `roots`. Every breakdown is sorted
+    /// largest first.
+    pub fn new(folders: &[FoundFolder], roots: &[PathBuf]) -> ScanStats {
+        let mut largest = folders.to_vec();
+        largest.sort_by_key(|folder| std::cmp::Reverse(folder.usage.allocated_bytes));
+
+        ScanStats {
+            total: folders.iter().map(|folder| folder.usage).sum(),
+            folders: folders.len(),
+            by_rule: buckets(folders, |folder| Some(folder.reason.to_string())),
+            by_ecosystem: buckets(folders, |folder| Some(Preset::ecosystem(&folder.reason))),
+            by_root: buckets(folders, |folder| root_of(&folder.path, roots).cloned()),
+            by_project: buckets(folders, |folder| {
+                folder.project_root().map(Path::to_path_buf)
+            }),
+            largest,
+        }
+    }
+}
+
+/// Groups `folders` by `key`, leaving out those without one.
+fn buckets<K: Hash + Eq + Clone>(
+    folders: &[FoundFolder],
+    key: impl Fn(&FoundFolder) -> Option<K>,
+) -> Vec<Bucket<K>> {
+    let mut index = HashMap::with_hasher(RandomState::new());
+    let mut buckets: Vec<Bucket<K>> = Vec::new();
+    for folder in folders {
+        let Some(key) = key(folder) else {
+            continue;
+        };
+        let i = *index.entry(key.clone()).or_insert_with(|| {
+            buckets.push(Bucket {
+                key,
+                folders: 0,
+                usage: DiskUsage::default(),
+            });
+            buckets.len() - 1
+        });
+        buckets[i].folders += 1;
+        buckets[i].usage += folder.usage;
+    }
+    buckets.sort_by_key(|bucket| std::cmp::Reverse(bucket.usage.allocated_bytes));
+    buckets
+}
@@ -0,0 +1,180 @@
+//! Moving folders to the trash, following the FreeDesktop.org Trash specification.
+//!
+//! Folders on the same filesystem as `$XDG_DATA_HOME` go to the home trash,
+//! everything else goes to the `.Trash/$uid` or `.Trash-$uid` directory at the
+//! top of the folder's mount.
+
+use std::{
+    env, fs,
+    io::{self, Write},
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{DirBuilderExt, MetadataExt},
+    },
+    path::{Path, PathBuf},
+};
+
+use crate::{dirfd, folderscan::FolderId};
+
+/// A trash directory containing `files` and `info`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TrashDir {
+    root: PathBuf,
+    /// Mount top directory that `Path=` entries are relative to, `None` for the home trash.
+    topdir: Option<PathBuf>,
+}
+
+/// Moves `path` into the matching trash directory and returns its new location.
+///
+/// The folder is renamed relative to its opened parent and only if it is still
+/// the directory identified by `expected`, see [`crate::dirfd`].
+pub fn move_to_trash(path: &Path, expected: Option<FolderId>) -> io::Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "cannot trash a root"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let trash = trash_dir_for(&path)?;
+    let files = trash.root.join("files");
+    let info = trash.root.join("info");
+    create_private_dir(&files)?;
+    create_private_dir(&info)?;
+
+    let trashed_path = match &trash.topdir {
+        Some(topdir) => path.strip_prefix(topdir).unwrap_or(&path),
+        None => &path,
+    };
+    let trash_info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode(trashed_path),
+        jiff::Zoned::now().strftime("%Y-%m-%dT%H:%M:%S")
+    );
+
+    for n in 0.. {
+        let entry_name = match n {
+            0 => name.clone(),
+            n => format!("{name}.{n}"),
+        };
+
+        // Creating the info file first reserves the name, as the spec requires.
+        let info_path = info.join(format!("{entry_name}.trashinfo"));
+        let mut info_file = match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        };
+
+        let target = files.join(&entry_name);
+        if target.symlink_metadata().is_ok() {
+            let _ = fs::remove_file(&info_path);
+            continue;
+        }
+
+        let moved = info_file
+            .write_all(trash_info.as_bytes())
+            .and_then(|_| dirfd::rename_folder(&path, expected, &target));
+        return match moved {
+            Ok(_) => Ok(target),
+            Err(e) => {
+                let _ = fs::remove_file(&info_path);
+                Err(e)
+            }
+        };
+    }
+    unreachable!("the trash name search is unbounded")
+}
+
+fn home_trash() -> io::Result<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "HOME is not set"))?;
+    Ok(data_home.join("Trash"))
+}
+
+fn trash_dir_for(path: &Path) -> io::Result<TrashDir> {
+    let device = path.symlink_metadata()?.dev();
+
+    let home = home_trash()?;
+    if fs::create_dir_all(&home).is_ok() && fs::metadata(&home)?.dev() == device {
+        return Ok(TrashDir {
+            root: home,
+            topdir: None,
+        });
+    }
+
+    let topdir = mount_root(path, device);
+    let uid = rustix::process::getuid().as_raw();
+
+    // An administrator provided `.Trash` must be a real, sticky directory.
+    let shared = topdir.join(".Trash");
+    let shared_is_valid = shared
+        .symlink_metadata()
+        .is_ok_and(|meta| meta.is_dir() && meta.mode() & 0o1000 != 0);
+    if shared_is_valid {
+        let root = shared.join(uid.to_string());
+        if create_private_dir(&root).is_ok() {
+            return Ok(TrashDir {
+                root,
+                topdir: Some(topdir),
+            });
+        }
+    }
+
+    let root = topdir.join(format!(".Trash-{uid}"));
+    create_private_dir(&root)?;
+    Ok(TrashDir {
+        root,
+        topdir: Some(topdir),
+    })
+}
+
+/// Returns the topmost ancestor of `path` that is still on `device`.
+fn mount_root(path: &Path, device: u64) -> PathBuf {
+    let mut root = path;
+    while let Some(parent) = root.parent() {
+        if !fs::metadata(parent).is_ok_and(|meta| meta.dev() == device) {
+            break;
+        }
+        root = parent;
+    }
+    root.to_path_buf()
+}
+
+/// Creates `dir` with mode `0700` if needed, refusing symlinks.
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    match fs::DirBuilder::new().mode(0o700).create(dir) {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            match dir.symlink_metadata()?.is_dir() {
+                true => Ok(()),
+                false => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} is not a directory", dir.display()),
+                )),
+            }
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Percent-encodes a path for the `Path=` key of a `.trashinfo` file.
+fn percent_encode(path: &Path) -> String {
+    path.as_os_str()
+        .as_bytes()
+        .iter()
+        .map(|&byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
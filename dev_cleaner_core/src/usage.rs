@@ -0,0 +1,268 @@
+use ahash::RandomState;
+use hashbrown::HashSet;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    fs::Metadata,
+    io,
+    ops::AddAssign,
+    path::Path,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+use walkdir::WalkDir;
+
+/// Disk usage of a directory tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiskUsage {
+    /// Sum of the file lengths.
+    pub apparent_bytes: u64,
+    /// Space actually allocated on disk, i.e. what removing the tree frees.
+    pub allocated_bytes: u64,
+    /// Number of non-directory entries.
+    pub file_count: u64,
+    /// Most recent modification time of any entry.
+    pub newest_mtime: Option<SystemTime>,
+}
+
+impl DiskUsage {
+    /// Measures the tree at `path` without following symlinks.
+    ///
+    /// Hard-linked files are only counted once. Entries that cannot be read
+    /// are skipped, only a failure to read `path` itself is returned.
+    pub fn of(path: impl AsRef<Path>) -> io::Result<DiskUsage> {
+        let path = path.as_ref();
+        path.symlink_metadata()?;
+
+        let mut usage = DiskUsage::default();
+        let mut seen_links: HashSet<(u64, u64), RandomState> =
+            HashSet::with_hasher(RandomState::new());
+
+        for entry in WalkDir::new(path).into_iter().filter_map(Result::ok) {
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if let Some(id) = hard_link_id(&meta)
+                && !seen_links.insert(id)
+            {
+                continue;
+            }
+            usage.add_entry(&meta);
+        }
+        Ok(usage)
+    }
+
+    pub(crate) fn add_entry(&mut self, meta: &Metadata) {
+        if !meta.is_dir() {
+            self.file_count += 1;
+            self.apparent_bytes += meta.len();
+        }
+        self.allocated_bytes += allocated_size(meta);
+        if let Ok(mtime) = meta.modified() {
+            self.newest_mtime = self.newest_mtime.max(Some(mtime));
+        }
+    }
+}
+
+impl AddAssign for DiskUsage {
+    fn add_assign(&mut self, rhs: Self) {
+        self.apparent_bytes += rhs.apparent_bytes;
+        self.allocated_bytes += rhs.allocated_bytes;
+        self.file_count += rhs.file_count;
+        self.newest_mtime = self.newest_mtime.max(rhs.newest_mtime);
+    }
+}
+
+impl std::iter::Sum for DiskUsage {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(DiskUsage::default(), |mut acc, usage| {
+            acc += usage;
+            acc
+        })
+    }
+}
+
+#[cfg(unix)]
+fn allocated_size(meta: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.blocks() * 512
+}
+
+#[cfg(not(unix))]
+fn allocated_size(meta: &Metadata) -> u64 {
+    meta.len()
+}
+
+#[cfg(unix)]
+pub(crate) fn hard_link_id(meta: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    (!meta.is_dir() && meta.nlink() > 1).then(|| (meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn hard_link_id(_meta: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// A byte count, displayed with binary units (e.g. `1.5 GiB`).
+///
+/// Parses sizes like `500K`, `1.5G` or `10 MiB`. All units are binary, so
+/// `1KB`, `1K` and `1KiB` are all 1024 bytes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "String", into = "String")]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    const UNITS: [&'static str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+}
+
+impl FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let (value, unit) = s.split_at(split);
+        let value: f64 = value
+            .parse()
+            .map_err(|_| format!("invalid size `{s}`, expected e.g. 500K, 10MiB or 1.5G"))?;
+
+        let unit = unit.trim();
+        let upper = unit.to_ascii_uppercase();
+        let exponent = match upper.trim_end_matches("IB").trim_end_matches('B') {
+            "" => 0,
+            "K" => 1,
+            "M" => 2,
+            "G" => 3,
+            "T" => 4,
+            "P" => 5,
+            _ => {
+                return Err(format!(
+                    "unknown size unit `{unit}`, use B, K, M, G, T or P"
+                ));
+            }
+        };
+        Ok(ByteSize((value * 1024f64.powi(exponent)) as u64))
+    }
+}
+
+impl TryFrom<String> for ByteSize {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ByteSize> for String {
+    /// Unlike [`Display`], uses the largest unit that represents the size exactly.
+    fn from(value: ByteSize) -> Self {
+        let mut bytes = value.0;
+        let mut unit = 0;
+        while bytes > 0 && bytes.is_multiple_of(1024) && unit < ByteSize::UNITS.len() - 1 {
+            bytes /= 1024;
+            unit += 1;
+        }
+        format!("{bytes}{}", ByteSize::UNITS[unit])
+    }
+}
+
+impl Display for ByteSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut value = self.0 as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < Self::UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        match unit {
+            0 => write!(f, "{} {}", self.0, Self::UNITS[0]),
+            _ => write!(f, "{value:.1} {}", Self::UNITS[unit]),
+        }
+    }
+}
+
+/// A duration written with a single unit, e.g. `30d`, `2w` or `12h`.
+///
+/// Supported units are `s`, `m` (minutes), `h`, `d`, `w`, `mo` (30 days) and
+/// `y` (365 days).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "String", into = "String")]
+pub struct Age(pub Duration);
+
+impl Age {
+    const UNITS: [(&'static str, u64); 7] = [
+        ("y", 365 * 86400),
+        ("mo", 30 * 86400),
+        ("w", 7 * 86400),
+        ("d", 86400),
+        ("h", 3600),
+        ("m", 60),
+        ("s", 1),
+    ];
+
+    /// Time elapsed since `time`, zero for times in the future.
+    pub fn since(time: SystemTime) -> Age {
+        Age(SystemTime::now().duration_since(time).unwrap_or_default())
+    }
+
+    /// Rounds down to the largest unit that fits, e.g. `3d` for 3 days and 5
+    /// hours. Months are skipped like in [`Display`].
+    pub fn rounded(self) -> Age {
+        let secs = self.0.as_secs();
+        let (_, seconds) = Self::UNITS
+            .iter()
+            .filter(|(unit, _)| *unit != "mo")
+            .find(|(_, seconds)| secs >= *seconds)
+            .unwrap_or(&("s", 1));
+        Age(Duration::from_secs(secs - secs % seconds))
+    }
+}
+
+impl FromStr for Age {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (value, unit) = s.split_at(split);
+        let value: u64 = value
+            .parse()
+            .map_err(|_| format!("invalid age `{s}`, expected e.g. 30d, 2w or 12h"))?;
+        let (_, seconds) = Self::UNITS
+            .iter()
+            .find(|(name, _)| *name == unit.trim())
+            .ok_or_else(|| format!("unknown age unit `{unit}`, use s, m, h, d, w, mo or y"))?;
+        Ok(Age(Duration::from_secs(value.saturating_mul(*seconds))))
+    }
+}
+
+impl TryFrom<String> for Age {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Age> for String {
+    fn from(value: Age) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for Age {
+    /// Uses the largest unit that represents the age exactly, months are
+    /// skipped so that `30d` stays `30d`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let secs = self.0.as_secs();
+        let (unit, seconds) = Self::UNITS
+            .iter()
+            .filter(|(unit, _)| *unit != "mo")
+            .find(|(_, seconds)| secs > 0 && secs.is_multiple_of(*seconds))
+            .unwrap_or(&("s", 1));
+        write!(f, "{}{unit}", secs / seconds)
+    }
+}
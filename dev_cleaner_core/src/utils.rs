@@ -25,20 +25,20 @@ where
 
     /// An iterator visiting all key-value pairs in arbitrary order.
     /// The iterator element type is `(&'a K, &'a V)`.
-    pub fn iter(&self) -> hashbrown::hash_map::Iter<K, V> {
+    pub fn iter(&self) -> hashbrown::hash_map::Iter<'_, K, V> {
         self.access.iter()
     }
 
     /// An iterator visiting all key-value pairs in arbitrary order,
     /// with mutable references to the values.
     /// The iterator element type is `(&'a K, &'a mut V)`.
-    pub fn iter_mut(&mut self) -> hashbrown::hash_map::IterMut<K, V> {
+    pub fn iter_mut(&mut self) -> hashbrown::hash_map::IterMut<'_, K, V> {
         self.access.iter_mut()
     }
 
     /// An iterator visiting all values mutably in arbitrary order.
     /// The iterator element type is `&'a mut V`.
-    pub fn values_mut(&mut self) -> hashbrown::hash_map::ValuesMut<K, V> {
+    pub fn values_mut(&mut self) -> hashbrown::hash_map::ValuesMut<'_, K, V> {
         self.access.values_mut()
     }
 
@@ -60,13 +60,13 @@ where
 
     /// An iterator visiting all keys in arbitrary order.
     /// The iterator element type is `&'a K`.
-    pub fn keys(&self) -> hashbrown::hash_map::Keys<K, V> {
+    pub fn keys(&self) -> hashbrown::hash_map::Keys<'_, K, V> {
         self.access.keys()
     }
 
     /// An iterator visiting all values in arbitrary order.
     /// The iterator element type is `&'a V`.
-    pub fn values(&self) -> hashbrown::hash_map::Values<K, V> {
+    pub fn values(&self) -> hashbrown::hash_map::Values<'_, K, V> {
         self.access.values()
     }
 
@@ -1,41 +1,329 @@
 use std::path::PathBuf;
 
-use iced::{widget::text, Element, Subscription, Task};
+use dev_cleaner_core::{
+    config::Config,
+    filters::FilterMatcher,
+    folderscan::{
+        CancellationToken, FoundFolder, ScanEvent, ScanProgress, remove_folders,
+        scan_folders_streaming,
+    },
+    remover::{RemovalMethod, RemovalReport},
+    safety::SafetyGuard,
+    usage::{ByteSize, DiskUsage},
+};
+use iced::{
+    Element, Fill, Size, Subscription, Task,
+    futures::{SinkExt, Stream, channel::oneshot, executor::block_on},
+    widget::{button, checkbox, column, row, scrollable, text, text_input},
+    window,
+};
 
 #[derive(Default)]
-#[allow(dead_code)]
 pub struct App {
     pub title: String,
     pub search_path: Option<PathBuf>,
+    config: Config,
+    path_input: String,
+    scan: Option<CancellationToken>,
+    progress: ScanProgress,
+    found: Vec<FoundFolder>,
+    selected: Vec<bool>,
+    removing: bool,
+    failures: Vec<String>,
+    status: String,
 }
 
 #[derive(Debug, Clone)]
 pub enum AppMessage {
     None,
+    WindowClosed(window::Id),
+    PathChanged(String),
+    StartScan,
+    CancelScan,
+    Scan(ScanEvent),
+    Toggle(usize, bool),
+    RemoveSelected,
+    Removed(RemovalReport),
 }
 
 impl App {
-    pub fn new(title: String, search_path: Option<PathBuf>) -> (Self, Task<AppMessage>) {
-        (Self { title, search_path },Task::none())
+    pub fn new(
+        title: String,
+        search_path: Option<PathBuf>,
+        config: Config,
+        size: (f32, f32),
+    ) -> (Self, Task<AppMessage>) {
+        let (_id, open) = window::open(window::Settings {
+            size: Size::new(size.0, size.1),
+            ..Default::default()
+        });
+        let open = open.map(|_| AppMessage::None);
+        let task = match search_path {
+            Some(_) => open.chain(Task::done(AppMessage::StartScan)),
+            None => open,
+        };
+
+        let app = Self {
+            title,
+            path_input: search_path
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default(),
+            search_path,
+            config,
+            ..Default::default()
+        };
+        (app, task)
     }
 
     pub fn subscription(&self) -> Subscription<AppMessage> {
-        iced::event::listen_with(|event, _, _id| {
-            if let iced::Event::Window(iced::window::Event::CloseRequested) = event {
-                // TODO: handle exit Some(AppMessage::Exit(id))
-                Some(AppMessage::None)
-            } else {
-                None
-            }
-        })
+        window::close_events().map(AppMessage::WindowClosed)
     }
 
-    pub fn update(&mut self, _message: AppMessage) -> Task<AppMessage> {
+    pub fn update(&mut self, message: AppMessage) -> Task<AppMessage> {
+        match message {
+            AppMessage::None => {}
+            AppMessage::WindowClosed(_id) => {
+                if let Some(cancel) = &self.scan {
+                    cancel.cancel();
+                }
+                return iced::exit();
+            }
+            AppMessage::PathChanged(path) => self.path_input = path,
+            AppMessage::StartScan => return self.start_scan(),
+            AppMessage::CancelScan => {
+                if let Some(cancel) = &self.scan {
+                    cancel.cancel();
+                }
+            }
+            AppMessage::Scan(ScanEvent::Progress(progress)) => self.progress = progress,
+            AppMessage::Scan(ScanEvent::Found(folder)) => {
+                self.found.push(folder);
+                self.selected.push(false);
+            }
+            AppMessage::Scan(ScanEvent::Finished {
+                progress,
+                cancelled,
+            }) => {
+                self.scan = None;
+                let total: DiskUsage = self.found.iter().map(|f| f.usage).sum();
+                self.status = format!(
+                    "{} after {} folders: {} matches, {}",
+                    if cancelled { "Cancelled" } else { "Finished" },
+                    progress.dirs_visited,
+                    self.found.len(),
+                    ByteSize(total.allocated_bytes)
+                );
+                let skipped = progress.too_recent + progress.too_small;
+                if skipped > 0 {
+                    self.status += &format!(" ({skipped} recent or small folders hidden)");
+                }
+                if !progress.skipped_mounts.is_empty() {
+                    let mounts = progress
+                        .skipped_mounts
+                        .iter()
+                        .map(|m| format!("{} ({})", m.path.display(), m.fs_type))
+                        .collect::<Vec<_>>();
+                    self.status += &format!(", skipped mounts: {}", mounts.join(", "));
+                }
+                self.progress = progress;
+            }
+            AppMessage::Toggle(index, selected) => {
+                if let Some(entry) = self.selected.get_mut(index) {
+                    *entry = selected;
+                }
+            }
+            AppMessage::RemoveSelected => return self.remove_selected(),
+            AppMessage::Removed(report) => {
+                self.removing = false;
+                self.failures = report
+                    .failed()
+                    .filter_map(|outcome| outcome.result.as_ref().err())
+                    .map(|error| format!("{} ({})", error.path.display(), error.kind))
+                    .chain(report.refused.iter().map(|refusal| {
+                        format!(
+                            "{} (refused: {})",
+                            refusal.folder.path.display(),
+                            refusal.violation
+                        )
+                    }))
+                    .collect();
+                let removed = report.removed().count();
+                let freed = ByteSize(report.bytes_freed());
+                let failed = self.failures.len();
+                self.status = match self.config.removal {
+                    RemovalMethod::Delete => format!(
+                        "Removed {removed} folders, freed {freed}, {failed} failed or refused"
+                    ),
+                    RemovalMethod::Trash => format!(
+                        "Moved {removed} folders to the trash, {freed} can be freed by emptying it, {failed} failed or refused"
+                    ),
+                };
+
+                let removed = report
+                    .removed()
+                    .map(|outcome| &outcome.folder.path)
+                    .collect::<Vec<_>>();
+                let (found, selected) = self
+                    .found
+                    .drain(..)
+                    .zip(self.selected.drain(..))
+                    .filter(|(folder, _)| !removed.contains(&&folder.path))
+                    .unzip();
+                self.found = found;
+                self.selected = selected;
+            }
+        }
         Task::none()
     }
 
+    fn remove_selected(&mut self) -> Task<AppMessage> {
+        if self.scan.is_some() || self.removing {
+            return Task::none();
+        }
+
+        let folders = self
+            .found
+            .iter()
+            .zip(&self.selected)
+            .filter(|(_, selected)| **selected)
+            .map(|(folder, _)| folder.clone())
+            .collect::<Vec<_>>();
+        if folders.is_empty() {
+            return Task::none();
+        }
+
+        // Without the filters folders could not be checked to still match
+        let filters = match self.config.matcher() {
+            Ok(filters) => filters,
+            Err(e) => {
+                self.status = e.to_string();
+                return Task::none();
+            }
+        };
+        let guard = SafetyGuard::new(self.search_path.clone())
+            .with_protected(self.config.protected.clone())
+            .with_filters(filters);
+        self.removing = true;
+        self.status = format!("Removing {} folders…", folders.len());
+        Task::perform(
+            remove_in_background(folders, self.config.removal, guard),
+            AppMessage::Removed,
+        )
+    }
+
+    fn start_scan(&mut self) -> Task<AppMessage> {
+        if self.scan.is_some() || self.path_input.trim().is_empty() {
+            return Task::none();
+        }
+
+        let filters = match self.config.matcher() {
+            Ok(filters) => filters,
+            Err(e) => {
+                self.status = e.to_string();
+                return Task::none();
+            }
+        };
+
+        let path = PathBuf::from(self.path_input.trim());
+        let cancel = CancellationToken::new();
+        self.search_path = Some(path.clone());
+        self.scan = Some(cancel.clone());
+        self.progress = ScanProgress::default();
+        self.found.clear();
+        self.selected.clear();
+        self.failures.clear();
+
+        Task::run(
+            scan_events(path, filters, self.config.threads, cancel),
+            AppMessage::Scan,
+        )
+    }
 
     pub fn view(&self, _id: iced::window::Id) -> Element<'_, AppMessage> {
-        text("Hello, world!").into()
+        let action = match self.scan.is_some() {
+            true => button("Cancel").on_press(AppMessage::CancelScan),
+            false => button("Scan").on_press(AppMessage::StartScan),
+        };
+        let header = row![
+            text_input("Folder to scan", &self.path_input)
+                .on_input(AppMessage::PathChanged)
+                .on_submit(AppMessage::StartScan),
+            action,
+        ]
+        .spacing(10);
+
+        let status = match self.scan.is_some() {
+            true => format!(
+                "Scanned {} folders, {} matches {}",
+                self.progress.dirs_visited,
+                self.progress.matches,
+                self.progress.current_path.display()
+            ),
+            false => self.status.clone(),
+        };
+
+        let results = self.found.iter().zip(&self.selected).enumerate().fold(
+            column![].spacing(4),
+            |results, (i, (f, selected))| {
+                results.push(row![
+                    checkbox(f.path.display().to_string(), *selected)
+                        .on_toggle(move |selected| AppMessage::Toggle(i, selected))
+                        .width(Fill),
+                    text(f.reason.to_string()).width(160),
+                    text(ByteSize(f.usage.allocated_bytes).to_string()),
+                ])
+            },
+        );
+
+        let can_remove = self.scan.is_none() && !self.removing && self.selected.contains(&true);
+        let remove = button("Remove selected")
+            .on_press_maybe(can_remove.then_some(AppMessage::RemoveSelected));
+
+        let failures = self.failures.iter().fold(column![], |failures, failure| {
+            failures.push(text(failure.clone()))
+        });
+
+        column![
+            header,
+            text(status),
+            scrollable(results).height(Fill),
+            failures,
+            remove
+        ]
+        .spacing(10)
+        .padding(10)
+        .into()
     }
 }
+
+/// Runs a streaming scan on a background thread, forwarding its events.
+fn scan_events(
+    path: PathBuf,
+    filters: FilterMatcher,
+    threads: usize,
+    cancel: CancellationToken,
+) -> impl Stream<Item = ScanEvent> {
+    iced::stream::channel(64, move |mut output| async move {
+        std::thread::spawn(move || {
+            for event in scan_folders_streaming(path, filters, threads, cancel) {
+                if block_on(output.send(event)).is_err() {
+                    break;
+                }
+            }
+        });
+    })
+}
+
+/// Removes `folders` on a background thread so the UI stays responsive.
+async fn remove_in_background(
+    folders: Vec<FoundFolder>,
+    method: RemovalMethod,
+    guard: SafetyGuard,
+) -> RemovalReport {
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(remove_folders(folders, method.remover().as_ref(), &guard));
+    });
+    receiver.await.unwrap_or_default()
+}
@@ -3,12 +3,13 @@ mod app;
 use std::path::PathBuf;
 
 use app::App;
+use dev_cleaner_core::config::Config;
 
-#[allow(dead_code)]
 pub struct DevCleanerGui {
     path: Option<PathBuf>,
     size: (f32, f32),
     title: String,
+    config: Config,
 }
 
 impl Default for DevCleanerGui {
@@ -17,6 +18,7 @@ impl Default for DevCleanerGui {
             path: None,
             size: (600.0, 800.0),
             title: String::from("DevCleaner"),
+            config: Config::default(),
         }
     }
 }
@@ -40,9 +42,13 @@ impl DevCleanerGui {
         }
     }
 
+    pub fn with_config(self, config: Config) -> Self {
+        DevCleanerGui { config, ..self }
+    }
+
     pub fn run(self) -> iced::Result {
         iced::daemon(|s: &App, _| s.title.clone(), App::update, App::view)
             .subscription(App::subscription)
-            .run_with(move || App::new(self.title, self.path))
+            .run_with(move || App::new(self.title, self.path, self.config, self.size))
     }
 }